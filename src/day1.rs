@@ -2,23 +2,14 @@ use std::collections::BTreeSet;
 
 use anyhow::Context;
 
-use crate::library::parse_items_ws;
-
-fn solve_recursive(values: &BTreeSet<i64>, min: i64, target: i64, depth: u32) -> Option<i64> {
-    match depth {
-        0 => None,
-        1 => values.get(&target).copied(),
-        depth => values.range(min..target).copied().find_map(|value| {
-            solve_recursive(values, value + 1, target - value, depth - 1)
-                .map(|solution| value * solution)
-        }),
-    }
-}
+use crate::library::{parse_items_ws, sums::find_subset_sum};
 
-fn solve(input: &str, depth: u32) -> anyhow::Result<i64> {
+fn solve(input: &str, count: usize) -> anyhow::Result<i64> {
     let values: BTreeSet<i64> = parse_items_ws(input)?;
 
-    solve_recursive(&values, 0, 2020, depth).context("The problem has no solution!")
+    find_subset_sum(&values, 2020, count)
+        .map(|subset| subset.iter().product())
+        .context("The problem has no solution!")
 }
 
 pub fn part1(input: &str) -> anyhow::Result<i64> {
@@ -28,3 +19,16 @@ pub fn part1(input: &str) -> anyhow::Result<i64> {
 pub fn part2(input: &str) -> anyhow::Result<i64> {
     solve(input, 3)
 }
+
+#[cfg(test)]
+const SAMPLE_INPUT: &str = "1721\n979\n366\n299\n675\n1456\n";
+
+#[test]
+fn test_part1_sample() {
+    assert_eq!(part1(SAMPLE_INPUT).unwrap(), 514579);
+}
+
+#[test]
+fn test_part2_sample() {
+    assert_eq!(part2(SAMPLE_INPUT).unwrap(), 241861950);
+}
@@ -1,10 +1,10 @@
 use std::{
     collections::{BTreeSet, HashMap},
-    convert::Infallible,
     ops::Bound,
 };
 
 use anyhow::{bail, Context};
+use thiserror::Error;
 
 use crate::library::{
     dynamic::{execute, Subtask, Task, TaskInterrupt},
@@ -89,7 +89,14 @@ struct Part2Solver {
     joltages: BTreeSet<i64>,
 }
 
-impl Task<Part2Goal, i64, Infallible> for Part2Solver {
+/// The number of arrangements grows exponentially with the number of
+/// adapters, so we count them in a u128 and report an error if even that
+/// overflows.
+#[derive(Debug, Clone, Copy, Error)]
+#[error("the number of arrangements is too large to count")]
+struct ArrangementOverflow;
+
+impl Task<Part2Goal, u128, ArrangementOverflow> for Part2Solver {
     type State = i64;
 
     fn solve<'sub, T>(
@@ -97,9 +104,9 @@ impl Task<Part2Goal, i64, Infallible> for Part2Solver {
         goal: &Part2Goal,
         subtasker: &'sub T,
         state: &mut Option<i64>,
-    ) -> Result<i64, TaskInterrupt<'sub, Part2Goal, Infallible>>
+    ) -> Result<u128, TaskInterrupt<'sub, Part2Goal, ArrangementOverflow>>
     where
-        T: Subtask<Part2Goal, i64>,
+        T: Subtask<Part2Goal, u128>,
     {
         let next_highest = match *state {
             None => {
@@ -133,11 +140,13 @@ impl Task<Part2Goal, i64, Infallible> for Part2Solver {
         let &num_arrangements_next_present = subtasker.solve(next_present)?;
         let &num_arrangements_next_absent = subtasker.solve(next_absent)?;
 
-        Ok(num_arrangements_next_present + num_arrangements_next_absent)
+        num_arrangements_next_present
+            .checked_add(num_arrangements_next_absent)
+            .ok_or(TaskInterrupt::Error(ArrangementOverflow))
     }
 }
 
-pub fn part2(input: &'static str) -> anyhow::Result<i64> {
+pub fn part2(input: &'static str) -> anyhow::Result<u128> {
     let task = Part2Solver {
         joltages: get_all_joltages(input)?,
     };
@@ -154,3 +163,36 @@ pub fn part2(input: &'static str) -> anyhow::Result<i64> {
 
     Ok(solution)
 }
+
+#[cfg(test)]
+const SAMPLE_INPUT: &str = "16\n10\n15\n5\n1\n11\n7\n19\n6\n12\n4\n";
+
+#[test]
+fn test_part1_sample() {
+    assert_eq!(part1(SAMPLE_INPUT).unwrap(), 7 * 5);
+}
+
+#[test]
+fn test_part2_sample() {
+    assert_eq!(part2(SAMPLE_INPUT).unwrap(), 8);
+}
+
+#[test]
+fn test_part2_long_chain() {
+    // With every joltage from 1 to 100 present, each adapter can be reached
+    // from any of the 3 before it
+    let mut ways: Vec<u128> = vec![1];
+
+    for joltage in 1..=100usize {
+        let start = joltage.saturating_sub(3);
+        ways.push(ways[start..joltage].iter().sum());
+    }
+
+    let expected = ways[100];
+    assert!(expected > i64::MAX as u128);
+
+    let input: String = (1..=100).map(|joltage| format!("{}\n", joltage)).collect();
+    let input: &'static str = Box::leak(input.into_boxed_str());
+
+    assert_eq!(part2(input).unwrap(), expected);
+}
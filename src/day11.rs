@@ -6,6 +6,8 @@ use gridly_grids::VecGrid;
 use itertools::Itertools;
 use thiserror::Error;
 
+use crate::library::grid::parse_grid;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Seat {
     Empty,
@@ -25,78 +27,19 @@ fn parse_seat(cell: char) -> Result<Option<Seat>, InvalidSeat> {
     }
 }
 
-pub fn part1(input: &str) -> anyhow::Result<usize> {
-    let lines = input.lines().map(|line| {
-        line.chars().map(|c| {
-            parse_seat(c)
-                .with_context(|| format!("Error parsing seat {:?}", c))
-                .unwrap()
-        })
-    });
-
-    let mut grid: VecGrid<Option<Seat>> =
-        VecGrid::new_from_rows(lines).context("Failed to create grid")?;
-
-    let mut scratch = grid.clone();
-
-    loop {
-        let mut changed = false;
-
-        for row in grid.rows().iter() {
-            for (target, cell) in row.iter_with_locations() {
-                if let Some(seat) = cell {
-                    match seat {
-                        Seat::Empty => {
-                            if TOUCHING_ADJACENCIES
-                                .iter()
-                                .map(|&dir| target + dir)
-                                .filter_map(|nearby| grid.get(nearby).ok())
-                                .all(|&nearby| nearby != Some(Seat::Occupied))
-                            {
-                                scratch.set(target, Some(Seat::Occupied)).unwrap();
-                                changed = true;
-                            } else {
-                                scratch.set(target, Some(Seat::Empty)).unwrap();
-                            }
-                        }
-                        Seat::Occupied => {
-                            if TOUCHING_ADJACENCIES
-                                .iter()
-                                .map(|&dir| target + dir)
-                                .filter_map(|nearby| grid.get(nearby).ok())
-                                .filter(|&&nearby| nearby == Some(Seat::Occupied))
-                                .count()
-                                >= 4
-                            {
-                                scratch.set(target, Some(Seat::Empty)).unwrap();
-                                changed = true;
-                            } else {
-                                scratch.set(target, Some(Seat::Occupied)).unwrap();
-                            }
-                        }
-                    }
-                }
-            }
-        }
-
-        mem::swap(&mut grid, &mut scratch);
-
-        if !changed {
-            break;
-        }
-    }
-
-    let occupied = grid
-        .rows()
-        .iter()
-        .flat_map(|row| row.iter())
-        .filter(|&&cell| cell == Some(Seat::Occupied))
-        .count();
-
-    Ok(occupied)
+fn read_grid(input: &str) -> anyhow::Result<VecGrid<Option<Seat>>> {
+    parse_grid(input, |c| Ok(parse_seat(c)?)).context("Failed to create grid")
 }
 
-fn scan_visible(grid: &impl Grid<Item = Option<Seat>>, target: Location) -> usize {
+/// Find the number of occupied seats visible from `target`, looking in each
+/// of the eight directions for the first seat. If `max_distance` is given, we
+/// look at most that many steps in each direction; `Some(1)` only considers
+/// the adjacent cells.
+fn scan_visible(
+    grid: &impl Grid<Item = Option<Seat>>,
+    target: Location,
+    max_distance: Option<usize>,
+) -> usize {
     TOUCHING_ADJACENCIES
         .iter()
         .copied()
@@ -104,6 +47,8 @@ fn scan_visible(grid: &impl Grid<Item = Option<Seat>>, target: Location) -> usiz
         .filter(|&direction| {
             // Create an iterator of locations moving in the given direction
             iter::successors(Some(target + direction), |&loc| Some(loc + direction))
+                // Up to the maximum sight distance
+                .take(max_distance.unwrap_or(usize::MAX))
                 // Get the cell at each location in this direction
                 .map(|location| grid.get(location).ok())
                 // While we're in the grid bounds
@@ -116,18 +61,14 @@ fn scan_visible(grid: &impl Grid<Item = Option<Seat>>, target: Location) -> usiz
         .count()
 }
 
-pub fn part2(input: &str) -> anyhow::Result<usize> {
-    let lines = input.lines().map(|line| {
-        line.chars().map(|c| {
-            parse_seat(c)
-                .with_context(|| format!("Error parsing seat {:?}", c))
-                .unwrap()
-        })
-    });
-
-    let mut grid: VecGrid<Option<Seat>> =
-        VecGrid::new_from_rows(lines).context("Failed to create grid")?;
-
+/// Run the seating simulation until it stabilizes, and return the number of
+/// occupied seats. An occupied seat is vacated if at least `threshold`
+/// occupied seats are visible from it, within `max_distance`.
+fn simulate(
+    mut grid: VecGrid<Option<Seat>>,
+    max_distance: Option<usize>,
+    threshold: usize,
+) -> usize {
     let mut scratch = grid.clone();
 
     loop {
@@ -138,7 +79,7 @@ pub fn part2(input: &str) -> anyhow::Result<usize> {
             .flat_map(|row| row.iter_with_locations())
             .filter_map(|(loc, cell)| cell.as_ref().map(|&seat| (loc, seat)))
             .for_each(|(target, seat): (Location, Seat)| {
-                let visible_occupied = scan_visible(&grid, target);
+                let visible_occupied = scan_visible(&grid, target, max_distance);
 
                 scratch
                     .set(
@@ -151,7 +92,7 @@ pub fn part2(input: &str) -> anyhow::Result<usize> {
                                 }
                                 false => Seat::Empty,
                             },
-                            Seat::Occupied => match visible_occupied >= 5 {
+                            Seat::Occupied => match visible_occupied >= threshold {
                                 true => {
                                     changed = true;
                                     Seat::Empty
@@ -170,12 +111,56 @@ pub fn part2(input: &str) -> anyhow::Result<usize> {
         }
     }
 
-    let seated = grid
-        .rows()
+    grid.rows()
         .iter()
         .flat_map(|row| row.iter())
         .filter(|&&cell| cell == Some(Seat::Occupied))
-        .count();
+        .count()
+}
+
+pub fn part1(input: &str) -> anyhow::Result<usize> {
+    read_grid(input).map(|grid| simulate(grid, Some(1), 4))
+}
+
+pub fn part2(input: &str) -> anyhow::Result<usize> {
+    read_grid(input).map(|grid| simulate(grid, None, 5))
+}
+
+#[cfg(test)]
+const SAMPLE_INPUT: &str = "\
+L.LL.LL.LL
+LLLLLLL.LL
+L.L.L..L..
+LLLL.LL.LL
+L.LL.LL.LL
+L.LLLLL.LL
+..L.L.....
+LLLLLLLLLL
+L.LLLLLL.L
+L.LLLLL.LL
+";
+
+#[test]
+fn test_simulate_adjacent() {
+    let grid = read_grid(SAMPLE_INPUT).unwrap();
+
+    assert_eq!(simulate(grid, Some(1), 4), 37);
+    assert_eq!(part1(SAMPLE_INPUT).unwrap(), 37);
+}
+
+#[test]
+fn test_part2_sample() {
+    assert_eq!(part2(SAMPLE_INPUT).unwrap(), 26);
+}
+
+#[test]
+fn test_scan_visible_max_distance() {
+    // The only occupied seat is 3 steps to the right of the empty seat
+    let grid = read_grid("L..#\n").unwrap();
+    let target = Location::zero();
 
-    Ok(seated)
+    assert_eq!(scan_visible(&grid, target, None), 1);
+    assert_eq!(scan_visible(&grid, target, Some(3)), 1);
+    assert_eq!(scan_visible(&grid, target, Some(2)), 0);
+    assert_eq!(scan_visible(&grid, target, Some(1)), 0);
 }
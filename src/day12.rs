@@ -2,14 +2,17 @@ use anyhow::Context;
 use gridly::prelude::*;
 use nom::{
     branch::alt,
-    character::complete::{char, digit1, multispace0, multispace1},
+    character::complete::{digit1, multispace0, multispace1, space0},
     IResult, Parser,
 };
 use nom_supreme::{
     error::ErrorTree, final_parser::final_parser, multi::parse_separated_terminated,
-    parse_from_str, parser_ext::ParserExt, tag::complete::tag,
+    parser_ext::ParserExt, tag::complete::tag,
 };
 
+use crate::library::nom::tag_case_insensitive;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Instruction {
     AbsoluteMove(Direction, isize),
     Turn(Rotation),
@@ -20,39 +23,39 @@ use Instruction::*;
 
 fn parse_direction(input: &str) -> IResult<&str, Direction, ErrorTree<&str>> {
     alt((
-        char('N').value(Up),
-        char('S').value(Down),
-        char('W').value(Left),
-        char('E').value(Right),
+        tag_case_insensitive("N").value(Up),
+        tag_case_insensitive("S").value(Down),
+        tag_case_insensitive("W").value(Left),
+        tag_case_insensitive("E").value(Right),
     ))
     .context("direction")
     .parse(input)
 }
 
 fn parse_rotation(input: &str) -> IResult<&str, Rotation, ErrorTree<&str>> {
-    alt((char('L').value(Anticlockwise), char('R').value(Clockwise)))
-        .and(alt((
-            tag("90").value(1),
-            tag("180").value(2),
-            tag("270").value(3),
-        )))
-        .map(|(rot, amount)| rot * amount)
-        .context("rotation")
-        .parse(input)
+    alt((
+        tag_case_insensitive("L").value(Anticlockwise),
+        tag_case_insensitive("R").value(Clockwise),
+    ))
+    .and(alt((tag("90").value(1), tag("180").value(2), tag("270").value(3))).preceded_by(space0))
+    .map(|(rot, amount)| rot * amount)
+    .context("rotation")
+    .parse(input)
 }
 
 fn parse_instruction(input: &str) -> IResult<&str, Instruction, ErrorTree<&str>> {
     alt((
         // Parse an absolute direction (N, E, S, W) and a magnitude
         parse_direction
-            .and(parse_from_str(digit1))
+            .and(digit1.parse_from_str().preceded_by(space0))
             .map(|(direction, distance)| AbsoluteMove(direction, distance))
             .context("absolute movement"),
         // Parse a rotation
         parse_rotation.map(Turn),
         // Parse "F" and a magnitude
-        parse_from_str(digit1)
-            .preceded_by(char('F'))
+        digit1
+            .parse_from_str()
+            .preceded_by(tag_case_insensitive("F").terminated(space0))
             .map(MoveForward)
             .context("relative movement"),
     ))
@@ -156,3 +159,20 @@ pub fn part2(input: &str) -> anyhow::Result<isize> {
         .context("Failed to execute all instructions")
         .map(|ship| (ship.location - Location::zero()).manhattan_length())
 }
+
+#[test]
+fn test_parse_instruction_forms() {
+    fn parse(input: &str) -> Result<Instruction, ErrorTree<nom_supreme::final_parser::Location>> {
+        final_parser(parse_instruction)(input)
+    }
+
+    assert_eq!(parse("N10").unwrap(), AbsoluteMove(Up, 10));
+    assert_eq!(parse("n10").unwrap(), AbsoluteMove(Up, 10));
+    assert_eq!(parse("w 3").unwrap(), AbsoluteMove(Left, 3));
+    assert_eq!(parse("R 90").unwrap(), Turn(Clockwise));
+    assert_eq!(parse("l270").unwrap(), Turn(Anticlockwise * 3));
+    assert_eq!(parse("f 7").unwrap(), MoveForward(7));
+
+    assert!(parse("R45").is_err());
+    assert!(parse("R 45").is_err());
+}
@@ -1,12 +1,18 @@
-use std::cmp::Ordering;
+use std::{
+    cmp::Ordering,
+    fmt::{self, Display, Formatter},
+};
 
 use anyhow::Context;
-use itertools::Itertools;
-use num::integer::lcm;
+use num::integer::gcd;
+use thiserror::Error;
 
-use crate::library::parse_items;
+use crate::library::{math::mod_inverse, parse_items};
 
-pub fn part1(input: &str) -> anyhow::Result<i64> {
+/// Find the first bus that departs at or after the earliest departure time.
+/// Returns that bus's ID and the number of minutes until it departs, which
+/// is 0 if it departs exactly at the earliest departure time.
+fn part1_detail(input: &str) -> anyhow::Result<(i64, i64)> {
     let mut parts = input.split_whitespace();
 
     let earliest_departure_time: i64 = parts
@@ -23,42 +29,175 @@ pub fn part1(input: &str) -> anyhow::Result<i64> {
 
     let schedule: Vec<i64> = parse_items(schedule).context("Failed to parse bus schedule")?;
 
-    let (target_departure, bus_id) = schedule
+    let (wait, bus_id) = schedule
         .iter()
-        .map(|&bus_id| {
-            let cycles = earliest_departure_time / bus_id;
-            let extra_time = earliest_departure_time % bus_id;
-
-            let departure = match extra_time {
-                0 => earliest_departure_time,
-                _ => (cycles + 1) * bus_id,
-            };
-
-            (departure, bus_id)
-        })
+        .map(|&bus_id| ((-earliest_departure_time).rem_euclid(bus_id), bus_id))
         .min()
         .context("No busses in the schedule")?;
 
-    Ok((target_departure - earliest_departure_time) * bus_id)
+    Ok((bus_id, wait))
+}
+
+pub fn part1(input: &str) -> anyhow::Result<i64> {
+    part1_detail(input).map(|(bus_id, wait)| bus_id * wait)
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 struct BusDesc {
     period: i64,
     offset: i64,
 }
 
-fn advance_candidate(target: i64, candidate: i64, period: i64) -> i64 {
-    let difference = target - candidate;
+impl BusDesc {
+    /// The earliest non-negative timestamp at which this schedule departs
+    fn timestamp(&self) -> i64 {
+        (-self.offset).rem_euclid(self.period)
+    }
+}
+
+/// Combined periods grow quickly, so all of the part 2 arithmetic is checked,
+/// rather than risking a silently wrong answer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("bus schedule arithmetic overflowed an i64")]
+struct Overflow;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+enum MergeError {
+    #[error(transparent)]
+    Overflow(#[from] Overflow),
+
+    #[error("busses {period1} and {period2} never depart at their offsets together")]
+    NeverAligned { period1: i64, period2: i64 },
+}
+
+fn checked_lcm(a: i64, b: i64) -> Result<i64, Overflow> {
+    (a / gcd(a, b)).checked_mul(b).ok_or(Overflow)
+}
+
+fn advance_candidate(target: i64, candidate: i64, period: i64) -> Result<i64, Overflow> {
+    let difference = target.checked_sub(candidate).ok_or(Overflow)?;
 
     let steps = difference.div_euclid(period);
     let extra = (difference.rem_euclid(period) != 0) as i64;
 
-    candidate + ((steps + extra) * period)
+    (steps + extra)
+        .checked_mul(period)
+        .and_then(|advance| candidate.checked_add(advance))
+        .ok_or(Overflow)
 }
 
-pub fn part2(input: &str) -> anyhow::Result<i64> {
-    let solution = input
+/// Combine two bus schedules into a single schedule that departs whenever
+/// both busses depart at their respective offsets. If the periods share a
+/// factor, the busses must agree modulo that factor, or they never line up.
+fn combine_busses(bus1: BusDesc, bus2: BusDesc) -> Result<BusDesc, MergeError> {
+    let combined_period = checked_lcm(bus1.period, bus2.period)?;
+
+    // When the periods are coprime (as they are in the puzzle input), we can
+    // directly solve for the earliest shared departure with a modular
+    // inverse, rather than searching for it.
+    if let Some(inverse) = mod_inverse(bus1.period, bus2.period) {
+        let departure1 = (-bus1.offset).rem_euclid(bus1.period);
+        let departure2 = (-bus2.offset).rem_euclid(bus2.period);
+
+        let steps = (departure2 - departure1)
+            .rem_euclid(bus2.period)
+            .checked_mul(inverse)
+            .ok_or(Overflow)?
+            % bus2.period;
+
+        let departure = steps
+            .checked_mul(bus1.period)
+            .and_then(|advance| departure1.checked_add(advance))
+            .ok_or(Overflow)?;
+
+        return Ok(BusDesc {
+            period: combined_period,
+            offset: combined_period - departure,
+        });
+    }
+
+    let divisor = gcd(bus1.period, bus2.period);
+
+    // Without this check, the search below would never find a match, and
+    // would only stop when the candidates overflow.
+    if (bus2.offset - bus1.offset) % divisor != 0 {
+        return Err(MergeError::NeverAligned {
+            period1: bus1.period,
+            period2: bus2.period,
+        });
+    }
+
+    let mut candidate1 = -bus1.offset;
+    let mut candidate2 = -bus2.offset;
+
+    loop {
+        match candidate1.cmp(&candidate2) {
+            Ordering::Equal => {
+                break Ok(BusDesc {
+                    period: combined_period,
+                    offset: combined_period - candidate1,
+                })
+            }
+            Ordering::Less => candidate1 = advance_candidate(candidate2, candidate1, bus1.period)?,
+            Ordering::Greater => {
+                candidate2 = advance_candidate(candidate1, candidate2, bus2.period)?
+            }
+        }
+    }
+}
+
+/// A single step of the part 2 solution: a bus, and the combined schedule of
+/// that bus with all of the busses before it.
+#[derive(Debug, Clone, Copy)]
+struct CrtStep {
+    bus: BusDesc,
+    combined: BusDesc,
+}
+
+/// The full breakdown of the part 2 solution. Starting from the first bus,
+/// each subsequent bus is merged into the combined schedule of all the
+/// busses before it.
+#[derive(Debug, Clone)]
+pub struct CrtExplanation {
+    first: BusDesc,
+    steps: Vec<CrtStep>,
+}
+
+impl CrtExplanation {
+    fn timestamp(&self) -> i64 {
+        self.steps
+            .last()
+            .map(|step| step.combined)
+            .unwrap_or(self.first)
+            .timestamp()
+    }
+}
+
+impl Display for CrtExplanation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "start with period {}, offset {}",
+            self.first.period, self.first.offset
+        )?;
+
+        for step in &self.steps {
+            writeln!(
+                f,
+                "merge period {}, offset {} => period {}, timestamp {}",
+                step.bus.period,
+                step.bus.offset,
+                step.combined.period,
+                step.combined.timestamp()
+            )?;
+        }
+
+        write!(f, "earliest timestamp: {}", self.timestamp())
+    }
+}
+
+fn parse_busses(input: &str) -> anyhow::Result<Vec<BusDesc>> {
+    Ok(input
         .split_whitespace()
         .nth(1)
         .context("No bus schedule found")?
@@ -70,32 +209,208 @@ pub fn part2(input: &str) -> anyhow::Result<i64> {
                 offset: index as i64,
             })
         })
-        .fold1(|bus1, bus2| {
-            let combined_period = lcm(bus1.period, bus2.period);
-
-            let mut candidate1 = -bus1.offset;
-            let mut candidate2 = -bus2.offset;
-
-            loop {
-                match candidate1.cmp(&candidate2) {
-                    Ordering::Equal => {
-                        break BusDesc {
-                            period: combined_period,
-                            offset: combined_period - candidate1,
-                        }
-                    }
-                    Ordering::Less => {
-                        candidate1 = advance_candidate(candidate2, candidate1, bus1.period)
-                    }
-                    Ordering::Greater => {
-                        candidate2 = advance_candidate(candidate1, candidate2, bus2.period)
-                    }
+        .collect())
+}
+
+/// Solve part 2 by merging the busses one at a time, recording each step.
+/// This is what the `--explain` flag prints.
+pub fn explain_part2(input: &str) -> anyhow::Result<CrtExplanation> {
+    let mut busses = parse_busses(input)?.into_iter();
+
+    let first = busses.next().context("No busses in schedule")?;
+
+    let mut combined = first;
+    let mut steps = Vec::new();
+
+    for bus in busses {
+        combined = combine_busses(combined, bus)
+            .with_context(|| format!("Failed to merge bus {}", bus.period))?;
+
+        steps.push(CrtStep { bus, combined });
+    }
+
+    Ok(CrtExplanation { first, steps })
+}
+
+pub fn part2(input: &str) -> anyhow::Result<i64> {
+    let mut busses = parse_busses(input)?.into_iter();
+    let first = busses.next().context("No busses in schedule")?;
+
+    let solution = busses.try_fold(first, |combined, bus| {
+        combine_busses(combined, bus).with_context(|| format!("Failed to merge bus {}", bus.period))
+    })?;
+
+    Ok(solution.timestamp())
+}
+
+/// Solve part 2 directly with the Chinese remainder theorem: the timestamp
+/// must be congruent to `-offset` modulo `period` for every bus. The periods
+/// don't need to be coprime, but if two of them share a factor, the busses
+/// must agree modulo that factor, or they never depart at the right offsets.
+/// This is used to cross-check `part2`, which merges the busses pairwise.
+#[cfg(test)]
+fn solve_part2_crt(input: &str) -> anyhow::Result<i64> {
+    let busses = parse_busses(input)?;
+
+    if busses.is_empty() {
+        anyhow::bail!("No busses in schedule")
+    }
+
+    // The set of timestamps satisfying every bus so far is every
+    // `modulus`th timestamp, starting at `residue`
+    let (residue, modulus) =
+        busses
+            .iter()
+            .try_fold((0, 1), |(residue, modulus): (i64, i64), bus| {
+                let target = (-bus.offset).rem_euclid(bus.period);
+                let difference = target - residue;
+                let divisor = gcd(modulus, bus.period);
+
+                if difference % divisor != 0 {
+                    anyhow::bail!(
+                        "bus {} at offset {} can never line up with the busses before it",
+                        bus.period,
+                        bus.offset
+                    );
                 }
-            }
-        })
-        .context("No busses in schedule")?;
 
-    Ok(solution.period - solution.offset)
+                let reduced_period = bus.period / divisor;
+                let inverse = mod_inverse(modulus / divisor, reduced_period)
+                    .expect("dividing out the gcd always leaves coprime numbers");
+
+                let steps = (difference / divisor)
+                    .rem_euclid(reduced_period)
+                    .checked_mul(inverse)
+                    .ok_or(Overflow)?
+                    % reduced_period;
+
+                let residue = steps
+                    .checked_mul(modulus)
+                    .and_then(|advance| residue.checked_add(advance))
+                    .ok_or(Overflow)?;
+
+                let modulus = modulus.checked_mul(reduced_period).ok_or(Overflow)?;
+
+                Ok((residue, modulus))
+            })?;
+
+    Ok(residue.rem_euclid(modulus))
+}
+
+#[cfg(test)]
+const SAMPLE_INPUT: &str = "939\n7,13,x,x,59,x,31,19\n";
+
+#[test]
+fn test_part1_sample() {
+    assert_eq!(part1(SAMPLE_INPUT).unwrap(), 295);
+}
+
+#[test]
+fn test_part1_detail() {
+    assert_eq!(part1_detail(SAMPLE_INPUT).unwrap(), (59, 5));
+
+    // 944 is a multiple of 59, so that bus can be taken without waiting
+    assert_eq!(part1_detail("944\n7,13,x,x,59,x,31,19\n").unwrap(), (59, 0));
+}
+
+#[test]
+fn test_part2_sample() {
+    assert_eq!(part2(SAMPLE_INPUT).unwrap(), 1068781);
+}
+
+#[test]
+fn test_solve_part2_crt() {
+    let examples = [
+        ("0\n17,x,13,19\n", 3417),
+        ("0\n67,7,59,61\n", 754018),
+        ("0\n67,x,7,59,61\n", 779210),
+        ("0\n67,7,x,59,61\n", 1261476),
+        ("0\n1789,37,47,1889\n", 1202161486),
+        (SAMPLE_INPUT, 1068781),
+    ];
+
+    for &(input, expected) in &examples {
+        assert_eq!(solve_part2_crt(input).unwrap(), expected);
+    }
+}
+
+#[test]
+fn test_crt_agrees_with_part2() {
+    let examples = [
+        "0\n17,x,13,19\n",
+        "0\n67,7,59,61\n",
+        "0\n67,x,7,59,61\n",
+        "0\n67,7,x,59,61\n",
+        "0\n1789,37,47,1889\n",
+        "0\n4,x,6\n",
+        SAMPLE_INPUT,
+    ];
+
+    for &input in &examples {
+        let expected = solve_part2_crt(input).unwrap();
+
+        assert_eq!(part2(input).unwrap(), expected, "input: {:?}", input);
+        assert_eq!(
+            explain_part2(input).unwrap().timestamp(),
+            expected,
+            "input: {:?}",
+            input
+        );
+    }
+}
+
+#[test]
+fn test_part2_single_bus() {
+    // The only bus departs at timestamp 0
+    assert_eq!(part2("0\n7\n").unwrap(), 0);
+    assert_eq!(solve_part2_crt("0\n7\n").unwrap(), 0);
+    assert_eq!(explain_part2("0\n7\n").unwrap().timestamp(), 0);
+}
+
+#[test]
+fn test_solve_part2_crt_not_coprime() {
+    // t % 4 == 0 and (t + 2) % 6 == 0
+    assert_eq!(solve_part2_crt("0\n4,x,6\n").unwrap(), 4);
+
+    // t is even, but t + 1 is also even
+    assert!(solve_part2_crt("0\n2,4\n").is_err());
+    assert!(explain_part2("0\n2,4\n").is_err());
+    assert!(part2("0\n2,4\n").is_err());
+}
+
+#[test]
+fn test_combine_busses_not_coprime() {
+    let bus = |period, offset| BusDesc { period, offset };
+
+    let combined = combine_busses(bus(4, 0), bus(6, 2)).unwrap();
+    assert_eq!(combined.period, 12);
+    assert_eq!(combined.timestamp(), 4);
+
+    assert_eq!(
+        combine_busses(bus(2, 0), bus(4, 1)).unwrap_err(),
+        MergeError::NeverAligned {
+            period1: 2,
+            period2: 4
+        }
+    );
+}
+
+#[test]
+fn test_part2_overflow() {
+    // The product of these periods is far larger than an i64
+    let input = "0\n1000000007,1000000009,1000000021\n";
+
+    assert!(part2(input).is_err());
+    assert!(explain_part2(input).is_err());
+    assert!(solve_part2_crt(input).is_err());
+}
+
+#[test]
+fn test_explain_part2_sample() {
+    let explanation = explain_part2(SAMPLE_INPUT).unwrap();
+
+    assert_eq!(explanation.timestamp(), 1068781);
+    assert_eq!(explanation.steps.len(), 4);
 }
 
 /*
@@ -6,7 +6,7 @@ use std::collections::HashMap;
 use anyhow::Context;
 use nom::{
     branch::alt,
-    character::complete::{char, digit1, multispace0, multispace1},
+    character::complete::{char, digit1},
     multi::fold_many_m_n,
     sequence::separated_pair,
     IResult, Parser,
@@ -14,12 +14,72 @@ use nom::{
 use nom_supreme::{
     error::ErrorTree,
     final_parser::{final_parser, Location},
-    multi::parse_separated_terminated,
-    parse_from_str,
     parser_ext::ParserExt,
     tag::complete::tag,
 };
 
+use crate::library::{
+    bits::{self, clear_bit, set_bit},
+    nom::parse_bounded_int,
+};
+
+/// The number of bits in addresses, values, and masks
+const ADDRESS_BITS: usize = 36;
+
+/// All of the bits in the address space
+const ADDRESS_MASK: i64 = (1 << ADDRESS_BITS) - 1;
+
+/// Masks are written most significant bit first. Get the index of the bit
+/// (where 0 is the least significant bit) for the `position`th character of
+/// a mask. Panics if the position is outside of the address space.
+fn mask_bit_index(position: usize) -> usize {
+    assert!(
+        position < ADDRESS_BITS,
+        "mask position {} is out of range for a {} bit address",
+        position,
+        ADDRESS_BITS
+    );
+
+    ADDRESS_BITS - 1 - position
+}
+
+/// Get a value with only the bit at `index` set, where 0 is the least
+/// significant bit. Panics if the index is outside of the address space.
+fn bit(index: usize) -> i64 {
+    assert!(
+        index < ADDRESS_BITS,
+        "bit index {} is out of range for a {} bit address",
+        index,
+        ADDRESS_BITS
+    );
+
+    bits::bit(index)
+}
+
+#[test]
+fn test_bit() {
+    assert_eq!(bit(0), 1);
+    assert_eq!(bit(35), 0x8_0000_0000);
+}
+
+#[test]
+#[should_panic(expected = "bit index 36 is out of range for a 36 bit address")]
+fn test_bit_out_of_range() {
+    bit(36);
+}
+
+#[test]
+fn test_mask_bit_index() {
+    assert_eq!(mask_bit_index(0), 35);
+    assert_eq!(mask_bit_index(35), 0);
+}
+
+#[test]
+#[should_panic(expected = "mask position 36 is out of range")]
+fn test_mask_bit_index_out_of_range() {
+    mask_bit_index(36);
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 enum MaskBit {
     Ignore,
@@ -59,24 +119,28 @@ struct Mask {
 
 impl Mask {
     fn apply(&self, value: i64) -> i64 {
+        // Masks are built from `bit`, so they never reach past the address
+        // space, and neither does the result.
+        debug_assert_eq!((self.mask | self.setting) & !ADDRESS_MASK, 0);
+
         (self.mask & value) | self.setting
     }
 }
 
 fn parse_mask(input: &str) -> IResult<&str, Mask, ErrorTree<&str>> {
     fold_many_m_n(
-        36,
-        36,
+        ADDRESS_BITS,
+        ADDRESS_BITS,
         parse_mask_bit,
-        (Mask::default(), 1i64 << 36),
+        (Mask::default(), 0),
         |(mut mask, idx), maskbit| {
-            let idx = idx >> 1;
+            let index = mask_bit_index(idx);
             match maskbit {
-                MaskBit::Ignore => mask.mask |= idx,
-                MaskBit::Set => mask.setting |= idx,
+                MaskBit::Ignore => mask.mask |= bit(index),
+                MaskBit::Set => mask.setting |= bit(index),
                 MaskBit::Clear => {}
             };
-            (mask, idx)
+            (mask, idx + 1)
         },
     )
     .map(|(mask, _)| mask)
@@ -112,11 +176,11 @@ struct Write {
 
 fn parse_write(input: &str) -> IResult<&str, Write, ErrorTree<&str>> {
     separated_pair(
-        parse_from_str(digit1)
+        parse_bounded_int(digit1)
             .preceded_by(char('['))
             .terminated(char(']')),
         tag(" = "),
-        parse_from_str(digit1),
+        parse_bounded_int(digit1),
     )
     .map(|(destination, value)| Write { destination, value })
     .context("write")
@@ -143,126 +207,47 @@ fn parse_instruction<'a, M>(
     .context("instruction")
 }
 
-#[derive(Debug, Default, Clone)]
-struct Machine {
-    mask: Mask,
-    memory: Vec<i64>,
-}
-
-impl Machine {
-    fn set_mask(&mut self, mask: Mask) {
-        self.mask = mask
-    }
-
-    fn write(&mut self, write: Write) {
-        let Write { destination, value } = write;
-
-        if destination >= self.memory.len() {
-            self.memory.resize(destination + 1, 0);
-        }
-
-        let value = self.mask.apply(value);
-
-        self.memory[destination] = value;
-    }
-
-    fn exec(&mut self, instruction: Instruction<Mask>) {
-        match instruction {
-            Instruction::SetMask(mask) => self.set_mask(mask),
-            Instruction::Write(write) => self.write(write),
-        }
-    }
-}
-
-pub fn part1(input: &str) -> anyhow::Result<i64> {
-    let result: Result<Machine, ErrorTree<Location>> = final_parser(
-        parse_separated_terminated(
-            parse_instruction(parse_mask),
-            multispace1,
-            multispace0.all_consuming(),
-            Machine::default,
-            |mut machine, instruction| {
-                machine.exec(instruction);
-                machine
-            },
-        )
-        .context("instruction list"),
-    )(input);
-
-    result
-        .context("Failed to execute machine")
-        .map(|machine| machine.memory.iter().copied().sum())
-}
-
 #[derive(Debug, Clone)]
 struct MemoryMask {
-    mask: [MaskBit; 36],
+    mask: [MaskBit; ADDRESS_BITS],
 }
 
 impl Default for MemoryMask {
     fn default() -> Self {
         Self {
-            mask: [MaskBit::Ignore; 36],
+            mask: [MaskBit::Ignore; ADDRESS_BITS],
         }
     }
 }
 
-#[derive(Debug, Clone, Default)]
-struct MachineV2 {
-    mask: MemoryMask,
-    memory: HashMap<i64, i64>,
-}
-
-impl MachineV2 {
-    fn set_mask(&mut self, mask: MemoryMask) {
-        self.mask = mask
-    }
-
-    fn write_recursive(&mut self, value: i64, dest: i64, depth: usize) {
-        match self.mask.mask.get(depth) {
+impl MemoryMask {
+    fn write_recursive(&self, memory: &mut Memory, value: i64, dest: i64, depth: usize) {
+        match self.mask.get(depth) {
             None => {
-                // eprintln!("write to {:#b}: {}", dest as i32, value);
-                self.memory.insert(dest, value);
+                memory.insert(dest as usize, value);
             }
             Some(MaskBit::Ignore) => {
-                let bit = 1 << (35 - depth);
+                let index = mask_bit_index(depth);
 
-                let dest = dest | bit;
-                self.write_recursive(value, dest, depth + 1);
-
-                let dest = dest - bit;
-                self.write_recursive(value, dest, depth + 1);
+                self.write_recursive(memory, value, set_bit(dest, index), depth + 1);
+                self.write_recursive(memory, value, clear_bit(dest, index), depth + 1);
             }
             Some(MaskBit::Set) => {
-                let bit = 1 << (35 - depth);
+                let index = mask_bit_index(depth);
 
-                let dest = dest | bit;
-                self.write_recursive(value, dest, depth + 1);
+                self.write_recursive(memory, value, set_bit(dest, index), depth + 1);
             }
             Some(MaskBit::Clear) => {
-                self.write_recursive(value, dest, depth + 1);
+                self.write_recursive(memory, value, dest, depth + 1);
             }
         }
     }
-
-    fn write(&mut self, write: Write) {
-        let Write { destination, value } = write;
-
-        self.write_recursive(value, destination as i64, 0);
-    }
-
-    fn exec(&mut self, instruction: Instruction<MemoryMask>) {
-        match instruction {
-            Instruction::SetMask(mask) => self.set_mask(mask),
-            Instruction::Write(write) => self.write(write),
-        }
-    }
 }
 
 fn parse_mem_mask(input: &str) -> IResult<&str, MemoryMask, ErrorTree<&str>> {
     fold_many_m_n(
-        36,
-        36,
+        ADDRESS_BITS,
+        ADDRESS_BITS,
         parse_mask_bit,
         (MemoryMask::default(), 0),
         |(mut mask, idx), maskbit| {
@@ -275,22 +260,161 @@ fn parse_mem_mask(input: &str) -> IResult<&str, MemoryMask, ErrorTree<&str>> {
     .parse(input)
 }
 
+/// The machine's memory. Version 2 writes are spread across the whole 36 bit
+/// address space, so it's sparse.
+type Memory = HashMap<usize, i64>;
+
+/// The two versions of the decoder chip differ only in their masks: the
+/// version 1 mask modifies the values being written, and the version 2 mask
+/// modifies the addresses being written to.
+pub trait MaskApply: Sized {
+    fn parse(input: &str) -> IResult<&str, Self, ErrorTree<&str>>;
+
+    /// Write `value` to `address` in `memory`, as modified by this mask
+    fn write(&self, memory: &mut Memory, address: usize, value: i64);
+}
+
+impl MaskApply for Mask {
+    fn parse(input: &str) -> IResult<&str, Self, ErrorTree<&str>> {
+        parse_mask(input)
+    }
+
+    fn write(&self, memory: &mut Memory, address: usize, value: i64) {
+        memory.insert(address, self.apply(value));
+    }
+}
+
+impl MaskApply for MemoryMask {
+    fn parse(input: &str) -> IResult<&str, Self, ErrorTree<&str>> {
+        parse_mem_mask(input)
+    }
+
+    fn write(&self, memory: &mut Memory, address: usize, value: i64) {
+        self.write_recursive(memory, value, address as i64, 0);
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Machine<M> {
+    mask: M,
+    memory: Memory,
+}
+
+impl<M: MaskApply> Machine<M> {
+    fn exec(&mut self, instruction: Instruction<M>) {
+        match instruction {
+            Instruction::SetMask(mask) => self.mask = mask,
+            Instruction::Write(Write { destination, value }) => {
+                self.mask.write(&mut self.memory, destination, value)
+            }
+        }
+    }
+
+    /// Parse and run a single instruction, like `mem[8] = 11`
+    pub fn exec_str(&mut self, line: &str) -> anyhow::Result<()> {
+        let instruction: Result<_, ErrorTree<Location>> =
+            final_parser(parse_instruction(M::parse))(line);
+
+        self.exec(instruction.context("Failed to parse instruction")?);
+        Ok(())
+    }
+
+    /// Read the value at a memory address. Addresses that were never written
+    /// to are 0.
+    #[cfg(test)]
+    pub fn read(&self, address: usize) -> i64 {
+        self.memory.get(&address).copied().unwrap_or(0)
+    }
+}
+
+/// Run a program, one line at a time, and return the sum of every value left
+/// in memory
+fn run<M: MaskApply + Default>(input: &str) -> anyhow::Result<i64> {
+    let mut machine = Machine::<M>::default();
+
+    for (index, line) in input.lines().enumerate() {
+        let line = line.trim();
+
+        if !line.is_empty() {
+            machine
+                .exec_str(line)
+                .with_context(|| format!("Failed to execute line {}", index + 1))?;
+        }
+    }
+
+    Ok(machine.memory.values().copied().sum())
+}
+
+pub fn part1(input: &str) -> anyhow::Result<i64> {
+    run::<Mask>(input)
+}
+
 pub fn part2(input: &str) -> anyhow::Result<i64> {
-    let result: Result<MachineV2, ErrorTree<Location>> = final_parser(
-        parse_separated_terminated(
-            parse_instruction(parse_mem_mask),
-            multispace1,
-            multispace0.all_consuming(),
-            MachineV2::default,
-            |mut machine, instruction| {
-                machine.exec(instruction);
-                machine
-            },
-        )
-        .context("instruction list"),
-    )(input);
-
-    result
-        .context("Failed to execute machine")
-        .map(|machine| machine.memory.values().copied().sum())
+    run::<MemoryMask>(input)
+}
+
+#[test]
+fn test_machine_exec_str() {
+    let mut machine = Machine::<Mask>::default();
+
+    machine
+        .exec_str("mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X")
+        .unwrap();
+    machine.exec_str("mem[8] = 11").unwrap();
+    machine.exec_str("mem[7] = 101").unwrap();
+
+    assert_eq!(machine.read(8), 73);
+    assert_eq!(machine.read(7), 101);
+    assert_eq!(machine.read(100), 0);
+
+    assert!(machine.exec_str("mem[8] 11").is_err());
+}
+
+#[test]
+fn test_machine_v2_floating_bits() {
+    let mut machine = Machine::<MemoryMask>::default();
+
+    machine
+        .exec_str("mask = 000000000000000000000000000000X1001X")
+        .unwrap();
+    machine.exec_str("mem[42] = 100").unwrap();
+
+    for &address in &[26, 27, 58, 59] {
+        assert_eq!(machine.read(address), 100, "address {}", address);
+    }
+    assert_eq!(machine.read(42), 0);
+
+    machine
+        .exec_str("mask = 00000000000000000000000000000000X0XX")
+        .unwrap();
+    machine.exec_str("mem[26] = 1").unwrap();
+
+    for &address in &[16, 17, 18, 19, 24, 25, 26, 27] {
+        assert_eq!(machine.read(address), 1, "address {}", address);
+    }
+    assert_eq!(machine.read(58), 100);
+}
+
+#[test]
+fn test_part1_sample() {
+    let input = concat!(
+        "mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X\n",
+        "mem[8] = 11\n",
+        "mem[7] = 101\n",
+        "mem[8] = 0\n",
+    );
+
+    assert_eq!(part1(input).unwrap(), 165);
+}
+
+#[test]
+fn test_part2_sample() {
+    let input = concat!(
+        "mask = 000000000000000000000000000000X1001X\n",
+        "mem[42] = 100\n",
+        "mask = 00000000000000000000000000000000X0XX\n",
+        "mem[26] = 1\n",
+    );
+
+    assert_eq!(part2(input).unwrap(), 208);
 }
@@ -1,36 +1,132 @@
-use std::collections::HashMap;
+use std::{convert::TryFrom, vec};
 
 use anyhow::Context;
 
-use crate::library::parse_items;
+use crate::library::{memory::check_allocation, parse_items_sep};
 
-fn solve_nth(input: &str, target: usize) -> anyhow::Result<usize> {
-    let values: Vec<usize> =
-        parse_items(input.split(',').map(|s| s.trim())).context("Failed to parse input")?;
+/// Sentinel in the last-seen table for a number that hasn't been spoken yet
+const NEVER: u32 = u32::MAX;
 
-    let mut records: HashMap<usize, (usize, usize)> = values
-        .iter()
-        .enumerate()
-        .map(|(idx, &value)| (value, (idx, idx)))
-        .collect();
-
-    let mut last_said = *values.last().unwrap();
-
-    for step in values.len()..target {
-        let &(last_seen1, last_seen2) = records.get(&last_said).context("Unrecorded value")?;
-        last_said = last_seen1 - last_seen2;
-        match records.entry(last_said) {
-            std::collections::hash_map::Entry::Occupied(mut entry) => {
-                let &(s1, _s2) = entry.get();
-                *entry.get_mut() = (step, s1);
-            }
-            std::collections::hash_map::Entry::Vacant(entry) => {
-                entry.insert((step, step));
+/// The numbers spoken in the memory game: first the starting numbers, then
+/// the computed numbers, forever. Turns are stored as u32 to halve the size
+/// of the last-seen table, so this panics after u32::MAX turns.
+#[derive(Debug, Clone)]
+struct Sequence {
+    starting_numbers: vec::IntoIter<usize>,
+
+    /// The turn on which each number was last spoken, not counting the most
+    /// recent turn.
+    last_seen: Vec<u32>,
+    last_said: Option<usize>,
+    turn: usize,
+}
+
+impl Iterator for Sequence {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let spoken = match self.starting_numbers.next() {
+            Some(value) => value,
+            None => match self.last_seen.get(self.last_said?) {
+                Some(&previous_turn) if previous_turn != NEVER => {
+                    self.turn - 1 - previous_turn as usize
+                }
+                _ => 0,
+            },
+        };
+
+        if let Some(last_said) = self.last_said {
+            if last_said >= self.last_seen.len() {
+                self.last_seen.resize(last_said + 1, NEVER);
             }
+
+            let turn = u32::try_from(self.turn - 1).expect("too many turns for the table");
+            self.last_seen[last_said] = turn;
         }
+
+        self.last_said = Some(spoken);
+        self.turn += 1;
+
+        Some(spoken)
     }
+}
+
+fn sequence(input: &str) -> anyhow::Result<impl Iterator<Item = usize>> {
+    let values: Vec<usize> = parse_items_sep(input, ',').context("Failed to parse input")?;
+
+    // The table has to fit every starting number, which can be much larger
+    // than anything that's spoken afterwards
+    let table_size = values
+        .iter()
+        .map(|&value| value.saturating_add(1))
+        .max()
+        .unwrap_or(0);
+
+    check_allocation::<u32>("spoken number table", table_size)?;
+
+    Ok(Sequence {
+        starting_numbers: values.into_iter(),
+        last_seen: vec![NEVER; table_size],
+        last_said: None,
+        turn: 0,
+    })
+}
+
+fn solve_nth(input: &str, target: usize) -> anyhow::Result<usize> {
+    let index = target.checked_sub(1).context("Turns start at 1")?;
+
+    // Turns are stored as u32 to halve the size of the table
+    u32::try_from(target).context("Too many turns for the last-seen table")?;
+
+    // Every number spoken after the starting numbers is the difference
+    // between two turns, so it's less than the target
+    check_allocation::<u32>("spoken number table", target)?;
+
+    sequence(input)?.nth(index).context("No starting numbers")
+}
+
+#[test]
+fn test_sequence() {
+    // Compute the sequence the slow way, by searching back through every
+    // previous number
+    let mut expected = vec![0, 3, 6];
+
+    while expected.len() < 500 {
+        let (&last, previous) = expected.split_last().unwrap();
+        let next = match previous.iter().rposition(|&value| value == last) {
+            Some(turn) => previous.len() - turn,
+            None => 0,
+        };
+        expected.push(next);
+    }
+
+    let actual: Vec<usize> = sequence("0,3,6").unwrap().take(500).collect();
+    assert_eq!(actual, expected);
+
+    assert_eq!(solve_nth("0,3,6", 2020).unwrap(), 436);
+}
+
+#[test]
+fn test_sequence_starting_numbers() {
+    // The starting numbers are spoken as-is, even when they repeat
+    let actual: Vec<usize> = sequence("4,4,100").unwrap().take(6).collect();
+    assert_eq!(actual, [4, 4, 100, 0, 0, 1]);
+}
+
+#[test]
+fn test_sequence_limits() {
+    use crate::library::memory::{set_max_memory, MemoryLimitExceeded};
+
+    set_max_memory(Some(1024 * 1024));
+    let result = sequence("1000000000000,0").map(|_| ());
+    set_max_memory(None);
+
+    assert!(result
+        .unwrap_err()
+        .downcast_ref::<MemoryLimitExceeded>()
+        .is_some());
 
-    Ok(last_said)
+    assert!(solve_nth("0,3,6", u32::MAX as usize + 1).is_err());
 }
 
 pub fn part1(input: &str) -> anyhow::Result<usize> {
@@ -1,6 +1,7 @@
 use std::{
     collections::HashSet,
     hash::{Hash, Hasher},
+    iter, ops,
 };
 
 use anyhow::Context;
@@ -10,18 +11,19 @@ use nom::{
     bytes::complete::is_not,
     character::complete::{char, digit1, multispace0, space0, space1},
     combinator::peek,
-    sequence::{separated_pair, tuple},
+    sequence::tuple,
     IResult, Parser,
 };
 use nom_supreme::{
     error::ErrorTree,
     final_parser::{final_parser, Location},
     multi::parse_separated_terminated,
-    parse_from_str,
     parser_ext::ParserExt,
     tag::complete::tag,
 };
 
+use crate::library::ranges;
+
 #[derive(Debug, Clone)]
 struct RangeInclusive {
     min: i64,
@@ -35,16 +37,77 @@ impl RangeInclusive {
 }
 
 fn parse_number(input: &str) -> IResult<&str, i64, ErrorTree<&str>> {
-    parse_from_str(digit1).context("number").parse(input)
+    digit1.parse_from_str().context("number").parse(input)
 }
 
+/// Parse a range like "3-10". If the dash is present but the upper bound
+/// isn't, report the error at the dash, rather than wherever `parse_number`
+/// happened to give up.
 fn parse_range(input: &str) -> IResult<&str, RangeInclusive, ErrorTree<&str>> {
-    separated_pair(parse_number, char('-'), parse_number)
+    // The dash is checked with `peek` outside of the context, so that a
+    // missing dash isn't reported as a missing upper bound.
+    let parse_upper_bound = parse_number
+        .cut()
+        .preceded_by(char('-'))
+        .context("expected upper bound after '-'")
+        .preceded_by(peek(char('-')));
+
+    parse_number
+        .context("lower bound")
+        .and(parse_upper_bound)
         .map(|(min, max)| RangeInclusive { min, max })
         .context("range")
         .parse(input)
 }
 
+#[cfg(test)]
+fn find_context<'a>(error: &ErrorTree<&'a str>, name: &str) -> Option<&'a str> {
+    use nom_supreme::error::StackContext;
+
+    match error {
+        ErrorTree::Base { .. } => None,
+        ErrorTree::Stack { base, contexts } => contexts
+            .iter()
+            .find(|(_, context)| matches!(context, StackContext::Context(context) if *context == name))
+            .map(|&(location, _)| location)
+            .or_else(|| find_context(base, name)),
+        ErrorTree::Alt(siblings) => siblings
+            .iter()
+            .find_map(|sibling| find_context(sibling, name)),
+    }
+}
+
+#[test]
+fn test_parse_range_missing_upper_bound() {
+    let input = "3-";
+
+    match parse_range(input) {
+        Err(nom::Err::Failure(error)) => {
+            let location = find_context(&error, "expected upper bound after '-'")
+                .expect("missing upper bound context");
+
+            // The context is reported at the dash
+            assert_eq!(location, "-");
+            assert_eq!(input.len() - location.len(), 1);
+        }
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn test_parse_range_missing_dash() {
+    match parse_range("3") {
+        Err(nom::Err::Error(error)) => {
+            assert_eq!(find_context(&error, "expected upper bound after '-'"), None)
+        }
+        result => panic!("unexpected result: {:?}", result),
+    }
+
+    let (tail, range) = parse_range("3-10 or").unwrap();
+    assert_eq!(tail, " or");
+    assert_eq!((range.min, range.max), (3, 10));
+}
+
 #[derive(Debug, Clone)]
 struct Rule {
     ranges: Vec<RangeInclusive>,
@@ -181,17 +244,130 @@ fn parse_day16_input(input: &str) -> Result<Day16Data, ErrorTree<Location>> {
 pub fn part1(input: &str) -> anyhow::Result<i64> {
     let input = parse_day16_input(input).context("Failed to parse input")?;
 
+    let all_ranges: Vec<ops::RangeInclusive<i64>> = input
+        .rules
+        .iter()
+        .flat_map(|rule| rule.rule.ranges.iter())
+        .map(|range| range.min..=range.max)
+        .collect();
+
+    // A field is invalid for every rule exactly when it falls outside of all
+    // of the values covered by any rule.
+    let valid_values = ranges::coverage(&all_ranges);
+
     let result = input
         .nearby_tickets
         .iter()
         .flat_map(|ticket| ticket.fields.iter().copied())
-        .filter(|&field| input.rules.iter().all(|rule| !rule.is_valid(field)))
+        .filter(|&field| {
+            valid_values
+                .iter()
+                .all(|&(min, max)| field < min || max < field)
+        })
         .sum();
 
     Ok(result)
 }
 
-pub fn part2(input: &str) -> anyhow::Result<i64> {
+/// Given the set of rules that each ticket column could match, assign each
+/// column a distinct rule. Columns with exactly one candidate are assigned
+/// first, removing that candidate from every other column, until every
+/// column is assigned. If this stalls, we instead try each candidate for the
+/// column with the fewest candidates, and repeat the process for each guess.
+/// Returns None if there is no valid assignment.
+///
+/// Note that an input with exactly one valid assignment never stalls, so the
+/// guessing only happens for inputs with several valid assignments, in which
+/// case the first one found is returned.
+fn assign_positions<T: Copy + Eq + Hash>(possibility_space: Vec<HashSet<T>>) -> Option<Vec<T>> {
+    let assigned = vec![None; possibility_space.len()];
+    assign_remaining_positions(possibility_space, assigned)
+}
+
+fn assign_remaining_positions<T: Copy + Eq + Hash>(
+    mut possibility_space: Vec<HashSet<T>>,
+    mut assigned: Vec<Option<T>>,
+) -> Option<Vec<T>> {
+    // Assigned candidates are removed from every column, including the
+    // column they were assigned to, so only unassigned columns are found here
+    while let Some((idx, rule)) = possibility_space
+        .iter()
+        .enumerate()
+        .find_map(|(idx, rules)| rules.iter().exactly_one().ok().map(|&rule| (idx, rule)))
+    {
+        assigned[idx] = Some(rule);
+        possibility_space.iter_mut().for_each(|rules| {
+            rules.remove(&rule);
+        });
+    }
+
+    let stalled_column = assigned
+        .iter()
+        .zip(&possibility_space)
+        .enumerate()
+        .filter(|(_, (assigned, _))| assigned.is_none())
+        .min_by_key(|(_, (_, rules))| rules.len())
+        .map(|(idx, _)| idx);
+
+    match stalled_column {
+        None => assigned.into_iter().collect(),
+        Some(idx) => possibility_space[idx].iter().find_map(|&rule| {
+            let mut possibility_space = possibility_space.clone();
+            possibility_space[idx] = iter::once(rule).collect();
+            assign_remaining_positions(possibility_space, assigned.clone())
+        }),
+    }
+}
+
+#[test]
+fn test_assign_positions_unique() {
+    let possibility_space = vec![
+        "ab".chars().collect(),
+        "abc".chars().collect(),
+        "a".chars().collect(),
+    ];
+
+    assert_eq!(
+        assign_positions(possibility_space),
+        Some(vec!['b', 'c', 'a'])
+    );
+}
+
+#[test]
+fn test_assign_positions_stalled() {
+    // After 'd' is assigned, every remaining column has two candidates
+    let possibility_space: Vec<HashSet<char>> = vec![
+        "d".chars().collect(),
+        "abd".chars().collect(),
+        "bc".chars().collect(),
+        "ac".chars().collect(),
+    ];
+
+    let assignment =
+        assign_positions(possibility_space.clone()).expect("should find an assignment");
+
+    assert_eq!(assignment[0], 'd');
+    assert_eq!(assignment.iter().unique().count(), 4);
+    assignment
+        .iter()
+        .zip(&possibility_space)
+        .for_each(|(rule, rules)| assert!(rules.contains(rule)));
+}
+
+#[test]
+fn test_assign_positions_impossible() {
+    let possibility_space = vec![
+        "a".chars().collect(),
+        "ab".chars().collect(),
+        "ab".chars().collect(),
+    ];
+
+    assert_eq!(assign_positions(possibility_space), None);
+}
+
+/// Find which field each column of the tickets is. Returns the field names
+/// in column order.
+pub fn solve_field_positions(input: &str) -> anyhow::Result<Vec<String>> {
     let input = parse_day16_input(input).context("Failed to parse input")?;
 
     let filtered_tickets = input.nearby_tickets.iter().filter(|&ticket| {
@@ -217,36 +393,48 @@ pub fn part2(input: &str) -> anyhow::Result<i64> {
             })
     });
 
-    let mut computed_rule_positions: Vec<Option<&FieldRule>> = vec![None; possibility_space.len()];
-
-    for _ in 0..computed_rule_positions.len() {
-        let (idx, rule) = possibility_space
-            .iter()
-            .enumerate()
-            .find_map(|(idx, rules)| rules.iter().exactly_one().ok().map(|&rule| (idx, rule)))
-            .context("No unique solution")?;
-
-        computed_rule_positions[idx] = Some(rule);
-        possibility_space.iter_mut().for_each(|rules| {
-            rules.remove(rule);
-        });
-    }
+    let computed_rule_positions =
+        assign_positions(possibility_space).context("No solution for the field positions")?;
 
-    let computed_rule_positions: anyhow::Result<Vec<&FieldRule>> = computed_rule_positions
+    Ok(computed_rule_positions
         .into_iter()
-        .enumerate()
-        .map(|(idx, rules)| rules.with_context(|| format!("No unique solution for index {}", idx)))
-        .collect();
+        .map(|rule| rule.name.to_owned())
+        .collect())
+}
 
-    let computed_rule_positions = computed_rule_positions?;
+#[test]
+fn test_solve_field_positions() {
+    let input = concat!(
+        "class: 0-1 or 4-19\n",
+        "row: 0-5 or 8-19\n",
+        "seat: 0-13 or 16-19\n",
+        "\n",
+        "your ticket:\n",
+        "11,12,13\n",
+        "\n",
+        "nearby tickets:\n",
+        "3,9,18\n",
+        "15,1,5\n",
+        "5,14,9\n",
+    );
+
+    assert_eq!(
+        solve_field_positions(input).unwrap(),
+        ["row", "class", "seat"]
+    );
+}
+
+pub fn part2(input: &str) -> anyhow::Result<i64> {
+    let field_positions = solve_field_positions(input)?;
+    let input = parse_day16_input(input).context("Failed to parse input")?;
 
     let result = input
         .your_ticket
         .fields
         .iter()
-        .zip(&computed_rule_positions)
-        .filter(|&(_field, &rule)| rule.name.starts_with("departure"))
-        .map(|(&field, _rule)| field)
+        .zip(&field_positions)
+        .filter(|&(_field, name)| name.starts_with("departure"))
+        .map(|(&field, _name)| field)
         .product();
 
     Ok(result)
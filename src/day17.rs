@@ -1,70 +1,137 @@
 use std::{collections::HashSet, iter};
 
 use anyhow::{bail, Context};
+use gridly::prelude::{
+    Column, Columns, Grid, GridBounds, Location as GridLocation, Row, Rows, Vector,
+};
+use itertools::Itertools;
+
+use crate::library::automaton;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct Location {
-    w: isize,
-    x: isize,
-    y: isize,
-    z: isize,
-}
-
-impl Location {
-    fn neighbors(self, include_w: bool) -> impl Iterator<Item = Location> {
-        (-1..=1)
-            .flat_map(|x| {
-                (-1..=1).flat_map(move |y| {
-                    (-1..=1).flat_map(move |z| (-1..=1).map(move |w| Location { w, x, y, z }))
-                })
-            })
-            .filter(move |loc| include_w || loc.w == 0)
-            .filter(|loc| loc.x != 0 || loc.y != 0 || loc.z != 0 || loc.w != 0)
-            .map(move |loc| Location {
-                x: self.x + loc.x,
-                y: self.y + loc.y,
-                z: self.z + loc.z,
-                w: self.w + loc.w,
+struct Location<const D: usize> {
+    coords: [isize; D],
+}
+
+impl<const D: usize> Location<D> {
+    fn neighbors(self) -> impl Iterator<Item = Location<D>> {
+        // Each offset is a D digit number in base 3, where the digits 0, 1,
+        // and 2 map to offsets of -1, 0, and 1 in that dimension.
+        (0..3usize.pow(D as u32))
+            .map(move |mut offset| {
+                let mut coords = self.coords;
+
+                for coord in &mut coords {
+                    *coord += (offset % 3) as isize - 1;
+                    offset /= 3;
+                }
+
+                Location { coords }
             })
+            .filter(move |&loc| loc != self)
     }
 }
 
 #[derive(Debug, Clone, Default)]
-struct ConwayCube {
-    cells: HashSet<Location>,
+struct ConwayCube<const D: usize> {
+    cells: HashSet<Location<D>>,
 }
 
-impl ConwayCube {
-    fn alive(&self, location: &Location) -> bool {
-        self.cells.contains(location)
+impl<const D: usize> ConwayCube<D> {
+    fn step(&self) -> ConwayCube<D> {
+        let cells = automaton::step(
+            &self.cells,
+            |loc| loc.neighbors(),
+            |alive, active_neighbors| matches!((alive, active_neighbors), (true, 2) | (_, 3)),
+        );
+
+        ConwayCube { cells }
     }
+}
 
-    fn step(&self, include_w: bool) -> ConwayCube {
-        let mut interesting_places = self.cells.clone();
-        interesting_places.extend(self.cells.iter().flat_map(|&loc| loc.neighbors(include_w)));
+/// A view of a single 2D plane of a `ConwayCube` as a gridly `Grid`. Rows
+/// and columns are the first two coordinates of the cube, and the remaining
+/// coordinates of the plane are taken from `plane` (its first two coordinates
+/// are ignored). The bounds of the grid cover every live cell in the cube, so
+/// every slice of a given cube has the same bounds.
+struct CubeSlice<'a, const D: usize> {
+    cube: &'a ConwayCube<D>,
+    plane: [isize; D],
+    root: GridLocation,
+    dimensions: Vector,
+}
 
-        let mut cells = HashSet::with_capacity(self.cells.len());
+impl<'a, const D: usize> CubeSlice<'a, D> {
+    fn new(cube: &'a ConwayCube<D>, plane: [isize; D]) -> Self {
+        let bounds = |axis: usize| {
+            cube.cells
+                .iter()
+                .map(|loc| loc.coords[axis])
+                .fold(None, |bounds, coord| match bounds {
+                    None => Some((coord, coord)),
+                    Some((min, max)) => Some((coord.min(min), coord.max(max))),
+                })
+                .unwrap_or((0, -1))
+        };
 
-        for loc in interesting_places {
-            let active_neighbors = loc
-                .neighbors(include_w)
-                .filter(|neighbor| self.alive(neighbor))
-                .count();
+        let (min_row, max_row) = bounds(0);
+        let (min_column, max_column) = bounds(1);
 
-            if self.alive(&loc) {
-                if let 2 | 3 = active_neighbors {
-                    cells.insert(loc);
-                }
-            } else if active_neighbors == 3 {
-                cells.insert(loc);
-            }
+        CubeSlice {
+            cube,
+            plane,
+            root: Row(min_row) + Column(min_column),
+            dimensions: Rows(max_row - min_row + 1) + Columns(max_column - min_column + 1),
         }
+    }
+}
 
-        ConwayCube { cells }
+impl<const D: usize> GridBounds for CubeSlice<'_, D> {
+    fn dimensions(&self) -> Vector {
+        self.dimensions
+    }
+
+    fn root(&self) -> GridLocation {
+        self.root
+    }
+}
+
+impl<const D: usize> Grid for CubeSlice<'_, D> {
+    type Item = bool;
+
+    unsafe fn get_unchecked(&self, location: GridLocation) -> &bool {
+        let mut coords = self.plane;
+        coords[0] = location.row.0;
+        coords[1] = location.column.0;
+
+        match self.cube.cells.contains(&Location { coords }) {
+            true => &true,
+            false => &false,
+        }
     }
 }
 
-fn parse_cube<I>(cells: I) -> anyhow::Result<ConwayCube>
+/// Render one plane of the cube, with `#` for live cells and `.` for dead
+/// ones. See `CubeSlice` for how `plane` is interpreted.
+fn render_slice<const D: usize>(cube: &ConwayCube<D>, plane: [isize; D]) -> String {
+    CubeSlice::new(cube, plane)
+        .rows()
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|&cell| match cell {
+                    true => '#',
+                    false => '.',
+                })
+                .chain(iter::once('\n'))
+                .collect::<String>()
+        })
+        .collect()
+}
+
+/// Parse the initial 2D slice of the cube. All of the other coordinates of the
+/// parsed cells are 0.
+fn parse_cube<I, const D: usize>(cells: I) -> anyhow::Result<ConwayCube<D>>
 where
     I: IntoIterator,
     I::Item: IntoIterator<Item = char>,
@@ -75,7 +142,10 @@ where
         for (y, cell) in (0..).zip(row) {
             match cell {
                 '#' => {
-                    cube.cells.insert(Location { x, y, z: 0, w: 0 });
+                    let mut coords = [0; D];
+                    coords[0] = x;
+                    coords[1] = y;
+                    cube.cells.insert(Location { coords });
                 }
                 '.' => {}
                 cell => bail!("Invalid cell {} at row {}, column {}", cell, x, y),
@@ -86,21 +156,68 @@ where
     Ok(cube)
 }
 
-fn solve(input: &'static str, include_w: bool) -> anyhow::Result<usize> {
-    let initial_cube = parse_cube(input.lines().map(|line| line.trim().chars()))
+/// Parse the cube and run it for the 6 cycles of the boot process
+fn boot<const D: usize>(input: &'static str) -> anyhow::Result<ConwayCube<D>> {
+    let initial_cube: ConwayCube<D> = parse_cube(input.lines().map(|line| line.trim().chars()))
         .context("Failed to parse cube")?;
 
-    let mut steps = iter::successors(Some(initial_cube), |cube| Some(cube.step(include_w)));
+    let mut steps = iter::successors(Some(initial_cube), |cube| Some(cube.step()));
 
-    let final_cube = steps.nth(6).unwrap();
+    Ok(steps.nth(6).unwrap())
+}
 
-    Ok(final_cube.cells.len())
+fn solve<const D: usize>(input: &'static str) -> anyhow::Result<usize> {
+    boot::<D>(input).map(|cube| cube.cells.len())
 }
 
 pub fn part1(input: &'static str) -> anyhow::Result<usize> {
-    solve(input, false)
+    solve::<3>(input)
+}
+
+/// Render every z plane of the 3D cube after booting, in the same format as
+/// the puzzle's examples. This is what the `--explain` flag prints for
+/// part 1.
+pub fn explain_part1(input: &'static str) -> anyhow::Result<String> {
+    let cube = boot::<3>(input)?;
+
+    let (min_z, max_z) = cube
+        .cells
+        .iter()
+        .map(|loc| loc.coords[2])
+        .minmax()
+        .into_option()
+        .context("No live cells after booting")?;
+
+    Ok((min_z..=max_z)
+        .map(|z| format!("z={}\n{}", z, render_slice(&cube, [0, 0, z])))
+        .join("\n"))
 }
 
 pub fn part2(input: &'static str) -> anyhow::Result<usize> {
-    solve(input, true)
+    solve::<4>(input)
+}
+
+#[test]
+fn test_cube_slice() {
+    let input = ".#.\n..#\n###";
+    let cube: ConwayCube<3> = parse_cube(input.lines().map(|line| line.chars())).unwrap();
+    let slice = CubeSlice::new(&cube, [0, 0, 0]);
+
+    let rows: Vec<Vec<bool>> = slice
+        .rows()
+        .iter()
+        .map(|row| row.iter().copied().collect())
+        .collect();
+
+    assert_eq!(
+        rows,
+        [
+            [false, true, false],
+            [false, false, true],
+            [true, true, true]
+        ]
+    );
+
+    assert_eq!(render_slice(&cube, [0, 0, 0]), ".#.\n..#\n###\n");
+    assert_eq!(render_slice(&cube, [0, 0, 1]), "...\n...\n...\n");
 }
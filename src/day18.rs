@@ -7,8 +7,6 @@ use nom::{
 use nom_supreme::{
     error::ErrorTree,
     final_parser::{final_parser, Location},
-    multi::parse_separated_terminated,
-    parse_from_str,
     parser_ext::ParserExt,
 };
 
@@ -37,22 +35,77 @@ fn parse_operator(input: &str) -> IResult<&str, Operator, ErrorTree<&str>> {
     .parse(input)
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expr {
+    Number(i64),
+    Operation {
+        lhs: Box<Expr>,
+        operator: Operator,
+        rhs: Box<Expr>,
+    },
+}
+
+impl Expr {
+    fn operation(lhs: Expr, operator: Operator, rhs: Expr) -> Self {
+        Expr::Operation {
+            lhs: Box::new(lhs),
+            operator,
+            rhs: Box::new(rhs),
+        }
+    }
+
+    fn evaluate(&self) -> i64 {
+        match *self {
+            Expr::Number(value) => value,
+            Expr::Operation {
+                ref lhs,
+                operator,
+                ref rhs,
+            } => operator.apply(lhs.evaluate(), rhs.evaluate()),
+        }
+    }
+
+    /// Parse a single expression, using the given operator precedence rules
+    fn parse(input: &str, precedence: Precedence) -> Result<Expr, ErrorTree<Location>> {
+        final_parser(|input| precedence.parse_expression(input))(input)
+    }
+}
+
+/// The operator precedence rules to use when parsing an expression
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Precedence {
+    /// All operators have the same precedence, and are applied left to right
+    FlatLeftToRight,
+
+    /// Addition is applied before multiplication
+    AddBeforeMul,
+}
+
+impl Precedence {
+    fn parse_expression<'a>(self, input: &'a str) -> IResult<&'a str, Expr, ErrorTree<&'a str>> {
+        match self {
+            Precedence::FlatLeftToRight => parse_expression(input),
+            Precedence::AddBeforeMul => parse_product_expression(input),
+        }
+    }
+}
+
 /// Parse a single number like 25
-fn parse_number(input: &str) -> IResult<&str, i64, ErrorTree<&str>> {
-    parse_from_str(digit1).parse(input)
+fn parse_number(input: &str) -> IResult<&str, Expr, ErrorTree<&str>> {
+    digit1.parse_from_str().map(Expr::Number).parse(input)
 }
 
 /// Parse a single number or a parenthesized expression
 fn parse_item<'a>(
-    expression: impl Parser<&'a str, i64, ErrorTree<&'a str>>,
-) -> impl Parser<&'a str, i64, ErrorTree<&'a str>> {
+    expression: impl Parser<&'a str, Expr, ErrorTree<&'a str>>,
+) -> impl Parser<&'a str, Expr, ErrorTree<&'a str>> {
     alt((parse_number, parse_parenthesized(expression))).context("item")
 }
 
 /// Parse a parenthesized expression using an expression parser
 fn parse_parenthesized<'a>(
-    expression: impl Parser<&'a str, i64, ErrorTree<&'a str>>,
-) -> impl Parser<&'a str, i64, ErrorTree<&'a str>> {
+    expression: impl Parser<&'a str, Expr, ErrorTree<&'a str>>,
+) -> impl Parser<&'a str, Expr, ErrorTree<&'a str>> {
     expression
         .preceded_by(char('(').terminated(multispace0))
         .terminated(char(')').preceded_by(multispace0))
@@ -91,51 +144,80 @@ fn parse_generic_expression<'a, O, T>(
 }
 
 /// Parse an expression with left-to-right operator precedence
-fn parse_expression(input: &str) -> IResult<&str, i64, ErrorTree<&str>> {
+fn parse_expression(input: &str) -> IResult<&str, Expr, ErrorTree<&str>> {
     parse_generic_expression(parse_item(parse_expression), parse_operator, |op, x, y| {
-        op.apply(x, y)
+        Expr::operation(x, op, y)
     })
     .parse(input)
 }
 
-fn parse_expression_list<'a>(
-    expression: impl Parser<&'a str, i64, ErrorTree<&'a str>>,
-) -> impl FnMut(&'a str) -> Result<i64, ErrorTree<Location>> {
-    final_parser(
-        parse_separated_terminated(
-            expression,
-            multispace0,
-            multispace0.all_consuming(),
-            || 0,
-            |sum, value| sum + value,
-        )
-        .context("expression list"),
-    )
+/// Parse and evaluate each line of the input as an expression, and sum the
+/// results
+fn solve(input: &str, precedence: Precedence) -> anyhow::Result<i64> {
+    input
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .enumerate()
+        .map(|(index, line)| {
+            Expr::parse(line.trim(), precedence)
+                .map(|expr| expr.evaluate())
+                .with_context(|| format!("Failed to parse expression {}", index))
+        })
+        .sum()
 }
 
 pub fn part1(input: &str) -> anyhow::Result<i64> {
-    parse_expression_list(parse_expression)(input).context("Failed to parse input")
+    solve(input, Precedence::FlatLeftToRight)
 }
 
 /// Parse a product expression, where each item is a sum expression
-fn parse_product_expression(input: &str) -> IResult<&str, i64, ErrorTree<&str>> {
-    parse_generic_expression(parse_sum_expression, char('*'), |_c, x, y| x * y)
-        .context("product expression")
-        .parse(input)
+fn parse_product_expression(input: &str) -> IResult<&str, Expr, ErrorTree<&str>> {
+    parse_generic_expression(parse_sum_expression, char('*'), |_c, x, y| {
+        Expr::operation(x, Operator::Times, y)
+    })
+    .context("product expression")
+    .parse(input)
 }
 
 /// Parse a sum expression, where each item is a single number or a parenthesized
 /// product expression
-fn parse_sum_expression(input: &str) -> IResult<&str, i64, ErrorTree<&str>> {
+fn parse_sum_expression(input: &str) -> IResult<&str, Expr, ErrorTree<&str>> {
     parse_generic_expression(
         parse_item(parse_product_expression),
         char('+'),
-        |_c, x, y| x + y,
+        |_c, x, y| Expr::operation(x, Operator::Plus, y),
     )
     .context("sum expression")
     .parse(input)
 }
 
 pub fn part2(input: &str) -> anyhow::Result<i64> {
-    parse_expression_list(parse_product_expression)(input).context("Failed to parse input")
+    solve(input, Precedence::AddBeforeMul)
+}
+
+#[test]
+fn test_precedence_shapes() {
+    use Operator::*;
+
+    let number = Expr::Number;
+
+    let flat = Expr::parse("1 * 2 + 3", Precedence::FlatLeftToRight).unwrap();
+    assert_eq!(
+        flat,
+        Expr::operation(
+            Expr::operation(number(1), Times, number(2)),
+            Plus,
+            number(3)
+        )
+    );
+
+    let add_first = Expr::parse("1 * 2 + 3", Precedence::AddBeforeMul).unwrap();
+    assert_eq!(
+        add_first,
+        Expr::operation(
+            number(1),
+            Times,
+            Expr::operation(number(2), Plus, number(3))
+        )
+    );
 }
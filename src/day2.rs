@@ -1,4 +1,4 @@
-use anyhow::Context;
+use anyhow::{anyhow, Context};
 use nom::{
     character::complete::multispace1,
     character::complete::space0,
@@ -106,8 +106,7 @@ pub fn part1(input: &str) -> anyhow::Result<usize> {
         .context("Error parsing input")?;
 
     tail.is_empty()
-        .then(|| solution)
-        .context("Didn't parse all of the input")
+        .then_or_else_err(solution, || anyhow!("Didn't parse all of the input"))
 }
 
 pub fn part2(input: &str) -> anyhow::Result<usize> {
@@ -124,6 +123,5 @@ pub fn part2(input: &str) -> anyhow::Result<usize> {
         .context("Error parsing input")?;
 
     tail.is_empty()
-        .then(|| solution)
-        .context("Didn't parse all of the input")
+        .then_or_else_err(solution, || anyhow!("Didn't parse all of the input"))
 }
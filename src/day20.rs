@@ -5,19 +5,9 @@ use std::{
 };
 
 use anyhow::{bail, Context};
-use cascade::cascade;
-use gridly::{prelude::*, range::CrossRange};
+use gridly::prelude::*;
 use gridly_adapters::{Translate, Window, ZeroRoot};
 use gridly_grids::{SparseGrid, VecGrid};
-use nom::{
-    bytes::complete::take_until,
-    character::complete::{digit1, multispace0, space1},
-    IResult, Parser,
-};
-use nom_supreme::{
-    error::ErrorTree, final_parser, multi::parse_separated_terminated, parse_from_str,
-    parser_ext::ParserExt, tag::complete::tag,
-};
 
 use library::BoolExt;
 
@@ -202,38 +192,27 @@ impl Hash for Tile {
     }
 }
 
-fn parse_tile(input: &str) -> IResult<&str, Tile, ErrorTree<&str>> {
-    parse_from_str(digit1)
-        .preceded_by(tag("Tile").terminated(space1))
-        .terminated(tag(":\n"))
-        .context("tile ID")
-        .and(take_until("\n\n").context("tile body"))
-        .map(|(id, grid_rows)| {
-            let lines = grid_rows.lines();
-            Tile {
-                id,
-                grid: VecGrid::new_from_rows(lines.map(|line| {
-                    line.chars().map(|c| match c {
-                        '#' => true,
-                        '.' => false,
-                        c => panic!("invalid grid character {:?}", c),
-                    })
-                }))
-                .expect("Error creating grid"),
-            }
-        })
-        .context("tile")
-        .parse(input)
-}
-
-fn parse_tile_list(input: &str) -> Result<Vec<Tile>, ErrorTree<final_parser::Location>> {
-    final_parser::final_parser(parse_separated_terminated(
-        parse_tile,
-        tag("\n\n"),
-        multispace0.all_consuming(),
-        Vec::new,
-        |vec, tile| cascade! {vec; ..push(tile);},
-    ))(input)
+fn parse_tile_list(input: &str) -> anyhow::Result<Vec<Tile>> {
+    let tiles = library::grid::labeled_grids(
+        input,
+        |header| {
+            header
+                .strip_prefix("Tile ")
+                .context("tile header doesn't start with \"Tile\"")?
+                .parse::<i64>()
+                .context("failed to parse tile ID")
+        },
+        |c| match c {
+            '#' => Ok(true),
+            '.' => Ok(false),
+            c => bail!("invalid grid character {:?}", c),
+        },
+    )?;
+
+    Ok(tiles
+        .into_iter()
+        .map(|(id, grid)| Tile { id, grid })
+        .collect())
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -290,8 +269,11 @@ pub fn part1(input: &str) -> anyhow::Result<i64> {
     Ok(corner_product)
 }
 
-pub fn part2(input: &str) -> anyhow::Result<usize> {
-    let tiles = parse_tile_list(input).context("Failed to parse tiles")?;
+/// Work out where each tile goes in the final image, and how it's oriented.
+/// Tiles are keyed by their offset from the first tile, measured in tiles;
+/// the first tile is placed at the origin with the default orientation.
+fn assemble(tiles: &[Tile]) -> anyhow::Result<HashMap<Vector, (i64, Orientation)>> {
+    let first_tile = tiles.first().context("No tiles to assemble")?;
 
     // All tiles, keyed by every known edge.
     let mut edge_db: HashMap<Edge, HashSet<&Tile>> = HashMap::new();
@@ -305,17 +287,15 @@ pub fn part2(input: &str) -> anyhow::Result<usize> {
     // All the tiles that haven't been places yet
     let mut unplaced: HashSet<&Tile> = tiles.iter().skip(1).collect();
 
-    // The final, rendered image
-    let mut final_image: SparseGrid<bool> =
-        SparseGrid::new_rooted(Row(-100) + Column(-100), Rows(200) + Columns(200));
+    // The tiles that have been placed so far
+    let mut placements = HashMap::with_capacity(tiles.len());
 
-    // This list of tiles which have been stamped, whose neighbors need to be
+    // This list of tiles which have been placed, whose neighbors need to be
     // explored
     let mut queue: VecDeque<(&Tile, Orientation, Vector)> = VecDeque::new();
 
     // The first tile is "canonical" in terms of orientation
-    let first_tile = tiles.first().unwrap();
-    stamp_tile(&mut final_image, Vector::zero(), &first_tile.grid);
+    placements.insert(Vector::zero(), (first_tile.id, Orientation::default()));
     queue.push_back((first_tile, Orientation::default(), Vector::zero()));
 
     while let Some((tile, orientation, offset)) = queue.pop_front() {
@@ -358,27 +338,45 @@ pub fn part2(input: &str) -> anyhow::Result<usize> {
                 })
                 .expect("Grid had no matching edge");
 
-            // Get the offset of the tile in the final image. We're hardcoding
-            // the knowledge that all tiles are 8x8 after removing edges.
-            let neighbor_offset = offset + (direction * 8);
-
-            // Stamp the tile
-            stamp_tile(
-                &mut final_image,
-                neighbor_offset,
-                &OrientedGrid {
-                    grid: &neighbor.grid,
-                    orientation: neighbor_orientation,
-                },
-            );
-
-            // This tile is now placed. Remove it from unplaced and add it to
-            // the queue.
+            // Place the tile
+            let neighbor_offset = offset + direction.as_vector();
+            placements.insert(neighbor_offset, (neighbor.id, neighbor_orientation));
+
+            // Remove it from unplaced and add it to the queue.
             unplaced.remove(neighbor);
             queue.push_back((neighbor, neighbor_orientation, neighbor_offset));
         }
     }
 
+    if !unplaced.is_empty() {
+        bail!("{} tiles couldn't be placed", unplaced.len());
+    }
+
+    Ok(placements)
+}
+
+pub fn part2(input: &str) -> anyhow::Result<usize> {
+    let tiles = parse_tile_list(input).context("Failed to parse tiles")?;
+    let placements = assemble(&tiles).context("Failed to assemble tiles")?;
+    let tiles_by_id: HashMap<i64, &Tile> = tiles.iter().map(|tile| (tile.id, tile)).collect();
+
+    // The final, rendered image
+    let mut final_image: SparseGrid<bool> =
+        SparseGrid::new_rooted(Row(-100) + Column(-100), Rows(200) + Columns(200));
+
+    for (&offset, &(id, orientation)) in &placements {
+        // Stamp the tile. We're hardcoding the knowledge that all tiles are
+        // 8x8 after removing edges.
+        stamp_tile(
+            &mut final_image,
+            offset * 8,
+            &OrientedGrid {
+                grid: &tiles_by_id[&id].grid,
+                orientation,
+            },
+        );
+    }
+
     // We now have a complete image. Scan it for sea serpents.
     // The problem didn't state this outright, but we're assuming that exactly
     // 1 orientation of the final image contains any sea serpents. First find
@@ -392,14 +390,7 @@ pub fn part2(input: &str) -> anyhow::Result<usize> {
                 orientation,
             };
 
-            let row_range = RowRange::span(grid.root_row(), grid.num_rows());
-            let col_range = ColumnRange::span(grid.root_column(), grid.num_columns());
-            let loc_range = CrossRange::new(row_range, col_range);
-
-            let mut windows =
-                loc_range.map(|root| Window::new(&grid, root, SeaSerpent.dimensions()));
-
-            windows.any(|window| SeaSerpent.contains_serpent(&window))
+            count_pattern(&grid, &SeaSerpent) > 0
         })
         .context("No serpents found in any orientation")?;
 
@@ -411,19 +402,16 @@ pub fn part2(input: &str) -> anyhow::Result<usize> {
         orientation: correct_orientation,
     };
 
-    let row_range = RowRange::span(grid.root_row(), grid.num_rows());
-    let col_range = ColumnRange::span(grid.root_column(), grid.num_columns());
-    let loc_range = CrossRange::new(row_range, col_range);
+    let roots: Vec<Location> = row_major_locations(&grid).collect();
 
-    for root in loc_range {
-        let window = Window::new(&mut grid, root, SeaSerpent.dimensions());
-        let mut window = ZeroRoot::new(window);
+    for root in roots {
+        if pattern_matches_at(&grid, &SeaSerpent, root) {
+            let offset = root - SeaSerpent.root();
 
-        if SeaSerpent.contains_serpent(&window) {
             for row in SeaSerpent.rows().iter() {
                 for (location, &body_part) in row.iter_with_locations() {
                     if body_part {
-                        window.set(location, false).unwrap();
+                        grid.set(location + offset, false).unwrap();
                     }
                 }
             }
@@ -441,28 +429,44 @@ pub fn part2(input: &str) -> anyhow::Result<usize> {
     Ok(count)
 }
 
-struct SeaSerpent;
+/// Iterate over all the locations in a grid in row-major order: left to right
+/// along each row, starting with the top row.
+fn row_major_locations(grid: &impl GridBounds) -> impl Iterator<Item = Location> {
+    let root = grid.root();
+    let dimensions = grid.dimensions();
 
-impl SeaSerpent {
-    fn contains_serpent(&self, grid: &impl Grid<Item = bool>) -> bool {
-        let grid = ZeroRoot::new(grid);
-
-        for row in self.rows().iter() {
-            for (location, &cell) in row.iter_with_locations() {
-                if cell {
-                    match grid.get(location) {
-                        Err(..) => return false,
-                        Ok(&false) => return false,
-                        _ => {}
-                    }
-                }
-            }
-        }
+    (0..dimensions.rows.0).flat_map(move |row| {
+        (0..dimensions.columns.0).map(move |column| root + Rows(row) + Columns(column))
+    })
+}
 
-        true
-    }
+/// Check if the pattern appears in the grid with its root at `root`; that is,
+/// if every set cell in the pattern is also set in the grid.
+fn pattern_matches_at(
+    grid: &impl Grid<Item = bool>,
+    pattern: &impl Grid<Item = bool>,
+    root: Location,
+) -> bool {
+    let offset = root - pattern.root();
+
+    pattern
+        .rows()
+        .iter()
+        .flat_map(|row| row.iter_with_locations())
+        .filter(|&(_, &cell)| cell)
+        .all(|(location, _)| matches!(grid.get(location + offset), Ok(&true)))
+}
+
+/// Count the number of places the pattern appears in the grid, scanning roots
+/// in row-major order. Overlapping appearances are each counted.
+fn count_pattern(grid: &impl Grid<Item = bool>, pattern: &impl Grid<Item = bool>) -> usize {
+    row_major_locations(grid)
+        .filter(|&root| pattern_matches_at(grid, pattern, root))
+        .count()
 }
 
+struct SeaSerpent;
+
 impl GridBounds for SeaSerpent {
     fn dimensions(&self) -> Vector {
         Rows(3) + Columns(20)
@@ -514,3 +518,54 @@ fn stamp_tile(
         }
     }
 }
+
+#[test]
+fn test_count_pattern() {
+    // Two serpents side by side, with a blank row above them
+    let rows: Vec<Vec<bool>> = std::iter::once(vec![false; 40])
+        .chain(SeaSerpent.rows().iter().map(|row| {
+            let row: Vec<bool> = row.iter().copied().collect();
+            row.iter().chain(&row).copied().collect()
+        }))
+        .collect();
+
+    let grid = VecGrid::new_from_rows(rows).unwrap();
+
+    assert_eq!(count_pattern(&grid, &SeaSerpent), 2);
+}
+
+#[test]
+fn test_assemble_places_every_tile_once() {
+    // Cut a pseudorandom 28x28 image into a 3x3 arrangement of 10x10 tiles,
+    // where neighboring tiles share their edge pixels.
+    let mut seed: u32 = 12345;
+    let image: Vec<Vec<bool>> = (0..28)
+        .map(|_| {
+            (0..28)
+                .map(|_| {
+                    seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+                    (seed >> 16) & 1 == 1
+                })
+                .collect()
+        })
+        .collect();
+
+    let tiles: Vec<Tile> = (0..3)
+        .flat_map(|tile_row| (0..3).map(move |tile_column| (tile_row, tile_column)))
+        .map(|(tile_row, tile_column)| Tile {
+            id: tile_row * 3 + tile_column,
+            grid: VecGrid::new_from_rows(
+                image[tile_row as usize * 9..][..10]
+                    .iter()
+                    .map(|row| row[tile_column as usize * 9..][..10].to_vec()),
+            )
+            .unwrap(),
+        })
+        .collect();
+
+    let placements = assemble(&tiles).unwrap();
+    let placed_ids: HashSet<i64> = placements.values().map(|&(id, _)| id).collect();
+
+    assert_eq!(placements.len(), tiles.len());
+    assert_eq!(placed_ids, tiles.iter().map(|tile| tile.id).collect());
+}
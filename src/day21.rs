@@ -21,13 +21,19 @@ use nom_supreme::{
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct Ingredient {
+pub struct Ingredient {
     name: &'static str,
 }
 
+impl Ingredient {
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
 impl Display for Ingredient {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.name.fmt(f)
+        self.name().fmt(f)
     }
 }
 
@@ -41,10 +47,16 @@ fn parse_ingredient(
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-struct Allergen {
+pub struct Allergen {
     name: &'static str,
 }
 
+impl Allergen {
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
 fn parse_allergen(input: &'static str) -> IResult<&'static str, Allergen, ErrorTree<&'static str>> {
     alpha1
         .map(|name| Allergen { name })
@@ -52,11 +64,24 @@ fn parse_allergen(input: &'static str) -> IResult<&'static str, Allergen, ErrorT
         .parse(input)
 }
 
-struct Recipe {
+#[derive(Debug, Clone)]
+pub struct Recipe {
     ingredients: HashSet<Ingredient>,
     known_allergens: HashSet<Allergen>,
 }
 
+impl Recipe {
+    pub fn ingredients(&self) -> &HashSet<Ingredient> {
+        &self.ingredients
+    }
+
+    /// The allergens listed on the recipe. Note that this may not be all of
+    /// the allergens actually present in the recipe's ingredients.
+    pub fn known_allergens(&self) -> &HashSet<Allergen> {
+        &self.known_allergens
+    }
+}
+
 fn parse_recipe(input: &'static str) -> IResult<&'static str, Recipe, ErrorTree<&'static str>> {
     let parse_allergen_list = parse_separated_terminated(
         parse_allergen,
@@ -103,7 +128,7 @@ fn parse_all_recipes(input: &'static str) -> Result<Vec<Recipe>, ErrorTree<Locat
 fn compute_allergens(recipes: &[Recipe]) -> anyhow::Result<HashMap<Ingredient, Allergen>> {
     let all_allergens: HashSet<Allergen> = recipes
         .iter()
-        .flat_map(|r| r.known_allergens.iter())
+        .flat_map(|r| r.known_allergens().iter())
         .copied()
         .collect();
 
@@ -112,14 +137,14 @@ fn compute_allergens(recipes: &[Recipe]) -> anyhow::Result<HashMap<Ingredient, A
     for &allergen in &all_allergens {
         let mut relevant_recipes = recipes
             .iter()
-            .filter(|r| r.known_allergens.contains(&allergen));
+            .filter(|r| r.known_allergens().contains(&allergen));
 
         let candidate_ingredients: HashSet<Ingredient> =
-            relevant_recipes.next().unwrap().ingredients.clone();
+            relevant_recipes.next().unwrap().ingredients().clone();
 
         let candidate_ingredients = relevant_recipes
             .fold(candidate_ingredients, |ingredients, recipe| {
-                &ingredients & &recipe.ingredients
+                &ingredients & recipe.ingredients()
             });
 
         allergen_candidate_map.insert(allergen, candidate_ingredients);
@@ -151,13 +176,24 @@ fn compute_allergens(recipes: &[Recipe]) -> anyhow::Result<HashMap<Ingredient, A
     Ok(allergen_map)
 }
 
-pub fn part1(input: &'static str) -> anyhow::Result<usize> {
+/// Parse the recipe list and determine which ingredient contains each
+/// allergen. Returns the parsed recipes along with the mapping, keyed by
+/// ingredient; ingredients absent from the mapping are allergen-free.
+pub fn solve_allergens(
+    input: &'static str,
+) -> anyhow::Result<(Vec<Recipe>, HashMap<Ingredient, Allergen>)> {
     let recipes = parse_all_recipes(input).context("Failed to parse all recipes")?;
     let allergen_map = compute_allergens(&recipes).context("Failed to compute allergens")?;
 
+    Ok((recipes, allergen_map))
+}
+
+pub fn part1(input: &'static str) -> anyhow::Result<usize> {
+    let (recipes, allergen_map) = solve_allergens(input)?;
+
     let instances_of_safe_ingredient = recipes
         .iter()
-        .flat_map(|r| r.ingredients.iter())
+        .flat_map(|r| r.ingredients().iter())
         .filter(|&ingredient| !allergen_map.contains_key(ingredient))
         .count();
 
@@ -165,11 +201,10 @@ pub fn part1(input: &'static str) -> anyhow::Result<usize> {
 }
 
 pub fn part2(input: &'static str) -> anyhow::Result<impl Display> {
-    let recipes = parse_all_recipes(input).context("Failed to parse all recipes")?;
-    let allergen_map = compute_allergens(&recipes).context("Failed to compute allergens")?;
+    let (_recipes, allergen_map) = solve_allergens(input)?;
 
     let mut ingredients: Vec<Ingredient> = allergen_map.keys().copied().collect();
-    ingredients.sort_unstable_by_key(|ingredient| allergen_map.get(ingredient));
+    ingredients.sort_unstable_by_key(|ingredient| allergen_map.get(ingredient).map(Allergen::name));
 
     Ok(ingredients.join_with(","))
 }
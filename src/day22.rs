@@ -1,6 +1,9 @@
-use std::collections::{HashSet, VecDeque};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    iter::FromIterator,
+};
 
-use anyhow::Context;
+use anyhow::{bail, Context};
 use cascade::cascade;
 use nom::{
     branch::alt,
@@ -15,9 +18,17 @@ use nom_supreme::{
     tag::complete::tag,
 };
 
+use crate::library::recursion::{with_depth_limit, Depth, DepthExceeded};
+
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 struct Card {
-    rank: usize,
+    rank: u8,
+}
+
+impl Card {
+    fn rank(&self) -> u8 {
+        self.rank
+    }
 }
 
 fn parse_card(input: &str) -> IResult<&str, Card, ErrorTree<&str>> {
@@ -65,16 +76,50 @@ impl Deck {
         }
     }
 
+    /// Iterate over the cards in the deck, from top to bottom
+    fn iter(&self) -> impl Iterator<Item = &Card> + DoubleEndedIterator {
+        self.cards.iter()
+    }
+
     fn score(&self) -> usize {
-        self.cards
-            .iter()
+        self.iter()
             .rev()
             .zip(1..)
-            .map(|(card, multiply)| card.rank * multiply)
+            .map(|(card, multiply)| usize::from(card.rank()) * multiply)
             .sum()
     }
 }
 
+/// Build a deck from card ranks, from top to bottom
+impl FromIterator<u8> for Deck {
+    fn from_iter<I: IntoIterator<Item = u8>>(ranks: I) -> Self {
+        Deck {
+            cards: ranks.into_iter().map(|rank| Card { rank }).collect(),
+        }
+    }
+}
+
+/// A compact, exact encoding of the state of a game: the length of the first
+/// deck, followed by the ranks of all the cards in both decks. This is much
+/// cheaper to build and hash than a pair of cloned decks, so it's used as the
+/// key when checking for repeated states.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct GameState(Box<[u8]>);
+
+impl GameState {
+    fn new(deck1: &Deck, deck2: &Deck) -> Self {
+        let len1 = deck1.len() as u32;
+
+        GameState(
+            len1.to_le_bytes()
+                .iter()
+                .copied()
+                .chain(deck1.iter().chain(deck2.iter()).map(Card::rank))
+                .collect(),
+        )
+    }
+}
+
 #[derive(Debug)]
 struct CardPreview<'a> {
     deck: &'a mut Deck,
@@ -114,8 +159,21 @@ fn parse_deck_pair(input: &str) -> Result<(Deck, Deck), ErrorTree<Location>> {
     )(input)
 }
 
-pub fn part1(input: &str) -> anyhow::Result<usize> {
-    let (mut deck1, mut deck2) = parse_deck_pair(input).context("Error parsing decks")?;
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Player {
+    Player1,
+    Player2,
+}
+
+use Player::*;
+
+/// Play a game of regular combat. Returns the winner and their score, or an
+/// error if the game repeats a previous state (in which case it would never
+/// terminate).
+fn play_combat(mut deck1: Deck, mut deck2: Deck) -> anyhow::Result<(Player, usize)> {
+    let mut game_states: HashSet<GameState> = HashSet::new();
+
+    game_states.insert(GameState::new(&deck1, &deck2));
 
     while let (Some(card1), Some(card2)) = (deck1.peek(), deck2.peek()) {
         let card1 = card1.draw();
@@ -126,37 +184,81 @@ pub fn part1(input: &str) -> anyhow::Result<usize> {
         } else {
             deck2.add_pair(card2, card1);
         }
+
+        if !game_states.insert(GameState::new(&deck1, &deck2)) {
+            bail!(
+                "Game repeated a previous state after {} rounds",
+                game_states.len()
+            );
+        }
     }
 
     if deck1.is_empty() {
-        Ok(deck2.score())
+        Ok((Player2, deck2.score()))
     } else {
-        Ok(deck1.score())
+        Ok((Player1, deck1.score()))
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
-enum Player {
-    Player1,
-    Player2,
+/// Play the game of regular combat described by the input. Returns the
+/// winner and their score.
+pub fn part1_winner(input: &str) -> anyhow::Result<(Player, usize)> {
+    let (deck1, deck2) = parse_deck_pair(input).context("Error parsing decks")?;
+
+    play_combat(deck1, deck2).context("Game never terminated")
 }
 
-use Player::*;
+pub fn part1(input: &str) -> anyhow::Result<usize> {
+    part1_winner(input).map(|(_, score)| score)
+}
+
+/// The deepest chain of nested sub-games we're willing to play. Each sub-game
+/// has strictly fewer cards than its parent, so well-formed inputs never come
+/// close to this.
+const MAX_GAME_DEPTH: usize = 1000;
 
-/// Returns true if deck 1 wins
-fn play_recursive_combat(mut deck1: Deck, mut deck2: Deck) -> (Player, usize) {
-    let mut game_states: HashSet<(Deck, Deck)> = HashSet::new();
+/// Returns the winner and their score. If given, `memo` caches the winners of
+/// previously played sub-games, keyed by their starting decks, so that
+/// identical sub-games aren't replayed.
+fn play_recursive_combat(
+    mut deck1: Deck,
+    mut deck2: Deck,
+    mut memo: Option<&mut HashMap<(Deck, Deck), Player>>,
+    depth: Depth,
+) -> Result<(Player, usize), DepthExceeded> {
+    let mut game_states: HashSet<GameState> = HashSet::new();
 
-    game_states.insert((deck1.clone(), deck2.clone()));
+    game_states.insert(GameState::new(&deck1, &deck2));
 
     while let (Some(card1), Some(card2)) = (deck1.peek(), deck2.peek()) {
         let card1 = card1.draw();
         let card2 = card2.draw();
 
-        let winner = if deck1.len() >= card1.rank && deck2.len() >= card2.rank {
-            let subdeck1 = deck1.try_clone(card1.rank).unwrap();
-            let subdeck2 = deck2.try_clone(card2.rank).unwrap();
-            play_recursive_combat(subdeck1, subdeck2).0
+        let rank1 = usize::from(card1.rank());
+        let rank2 = usize::from(card2.rank());
+
+        let winner = if deck1.len() >= rank1 && deck2.len() >= rank2 {
+            let subdeck1 = deck1.try_clone(rank1).unwrap();
+            let subdeck2 = deck2.try_clone(rank2).unwrap();
+            let key = (subdeck1, subdeck2);
+
+            match memo.as_ref().and_then(|memo| memo.get(&key)).copied() {
+                Some(winner) => winner,
+                None => {
+                    let (winner, _) = play_recursive_combat(
+                        key.0.clone(),
+                        key.1.clone(),
+                        memo.as_deref_mut(),
+                        depth.descend()?,
+                    )?;
+
+                    if let Some(memo) = memo.as_deref_mut() {
+                        memo.insert(key, winner);
+                    }
+
+                    winner
+                }
+            }
         } else if card1 > card2 {
             Player1
         } else {
@@ -168,22 +270,117 @@ fn play_recursive_combat(mut deck1: Deck, mut deck2: Deck) -> (Player, usize) {
             Player2 => deck2.add_pair(card2, card1),
         }
 
-        if !game_states.insert((deck1.clone(), deck2.clone())) {
-            return (Player1, deck1.score());
+        if !game_states.insert(GameState::new(&deck1, &deck2)) {
+            return Ok((Player1, deck1.score()));
         }
     }
 
     if deck1.is_empty() {
-        (Player2, deck2.score())
+        Ok((Player2, deck2.score()))
     } else {
-        (Player1, deck1.score())
+        Ok((Player1, deck1.score()))
     }
 }
 
 pub fn part2(input: &str) -> anyhow::Result<usize> {
     let (deck1, deck2) = parse_deck_pair(input).context("Error parsing decks")?;
 
-    let (_, score) = play_recursive_combat(deck1, deck2);
+    let (_, score) = with_depth_limit(MAX_GAME_DEPTH, |depth| {
+        play_recursive_combat(deck1, deck2, Some(&mut HashMap::new()), depth)
+    })
+    .context("Game recursed too deeply")?;
 
     Ok(score)
 }
+
+#[cfg(test)]
+const SAMPLE_INPUT: &str = "\
+Player 1:
+9
+2
+6
+3
+1
+
+Player 2:
+5
+8
+4
+7
+10
+";
+
+#[test]
+fn test_part1_sample() {
+    assert_eq!(part1(SAMPLE_INPUT).unwrap(), 306);
+    assert_eq!(part1_winner(SAMPLE_INPUT).unwrap(), (Player2, 306));
+}
+
+#[test]
+fn test_play_combat_cycle() {
+    // These decks return to an earlier state after a few rounds
+    let deck1: Deck = vec![1, 2, 4].into_iter().collect();
+    let deck2: Deck = vec![3, 5].into_iter().collect();
+
+    assert!(play_combat(deck1, deck2).is_err());
+}
+
+#[test]
+fn test_part2_sample() {
+    assert_eq!(part2(SAMPLE_INPUT).unwrap(), 291);
+}
+
+#[test]
+fn test_recursive_combat_memo() {
+    let (deck1, deck2) = parse_deck_pair(SAMPLE_INPUT).unwrap();
+    let mut memo = HashMap::new();
+
+    let memoized = with_depth_limit(MAX_GAME_DEPTH, |depth| {
+        play_recursive_combat(deck1.clone(), deck2.clone(), Some(&mut memo), depth)
+    })
+    .unwrap();
+
+    let unmemoized = with_depth_limit(MAX_GAME_DEPTH, |depth| {
+        play_recursive_combat(deck1, deck2, None, depth)
+    })
+    .unwrap();
+
+    assert_eq!(memoized, (Player2, 291));
+    assert_eq!(memoized, unmemoized);
+    assert!(!memo.is_empty());
+}
+
+#[test]
+fn test_deck_from_ranks() {
+    let deck: Deck = vec![3, 2, 10, 6, 8, 5, 9, 4, 7, 1].into_iter().collect();
+
+    assert_eq!(deck.len(), 10);
+    assert_eq!(
+        deck.iter().map(Card::rank).collect::<Vec<u8>>(),
+        [3, 2, 10, 6, 8, 5, 9, 4, 7, 1]
+    );
+
+    // The winning deck from the part 1 example
+    assert_eq!(deck.score(), 306);
+    assert_eq!(Deck::default().score(), 0);
+}
+
+#[test]
+fn test_game_state() {
+    let deck = |ranks: &[u8]| ranks.iter().copied().collect::<Deck>();
+
+    assert_eq!(
+        GameState::new(&deck(&[9, 2, 6]), &deck(&[5, 8])),
+        GameState::new(&deck(&[9, 2, 6]), &deck(&[5, 8]))
+    );
+
+    assert_ne!(
+        GameState::new(&deck(&[9, 2, 6]), &deck(&[5, 8])),
+        GameState::new(&deck(&[9, 2]), &deck(&[6, 5, 8]))
+    );
+
+    assert_ne!(
+        GameState::new(&deck(&[9, 2, 6]), &deck(&[5, 8])),
+        GameState::new(&deck(&[5, 8]), &deck(&[9, 2, 6]))
+    );
+}
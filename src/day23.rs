@@ -1,7 +1,10 @@
 use std::{cmp::max, collections::HashMap, fmt::Display};
 
+use anyhow::{bail, Context};
 use lazy_format::{lazy_format, make_lazy_format};
 
+use crate::library::{memory::check_allocation, parse::digits};
+
 #[derive(Debug, Clone, Default)]
 struct CupNode {
     next: usize,
@@ -73,9 +76,11 @@ impl CupLoop {
 
     fn print_ref(&self) -> impl Display + '_ {
         make_lazy_format!(fmt => {
+            // cups[0] is unused, since cups are labeled starting from 1
+            let num_cups = self.cups.len() - 1;
             let mut cup = 1;
 
-            for _ in 0..8 {
+            for _ in 1..num_cups {
                 cup = self.next_cup(cup);
                 write!(fmt, "{}", cup)?;
             }
@@ -85,11 +90,15 @@ impl CupLoop {
     }
 }
 
-fn run_simulation(input: &str, max_cup: usize, rounds: usize) -> CupLoop {
-    let mut input_cups = input
-        .trim()
-        .chars()
-        .map(|c| c.to_digit(10).unwrap() as usize);
+/// Run the simulation. `input_cups` must not be empty, and must be labeled
+/// 1 through N, for some N no larger than `max_cup`. The remaining cups up to
+/// `max_cup` are added after the input cups.
+fn run_simulation(input_cups: &[u8], max_cup: usize, rounds: usize) -> anyhow::Result<CupLoop> {
+    check_allocation::<CupNode>("cup loop", max_cup.saturating_add(1))?;
+
+    let largest_label = input_cups.iter().copied().max().map_or(0, usize::from);
+
+    let mut input_cups = input_cups.iter().map(|&cup| usize::from(cup));
 
     let mut cups = CupLoop::default();
 
@@ -104,7 +113,7 @@ fn run_simulation(input: &str, max_cup: usize, rounds: usize) -> CupLoop {
             current_cup = next_cup;
         }
 
-        for i in 10..=max_cup {
+        for i in largest_label + 1..=max_cup {
             cups.insert_after(current_cup, i);
             current_cup = i
         }
@@ -145,23 +154,124 @@ fn run_simulation(input: &str, max_cup: usize, rounds: usize) -> CupLoop {
         current_cup = cups.next_cup(current_cup);
     }
 
-    cups
+    Ok(cups)
+}
+
+/// The input is a line of cup labels, optionally followed by a line with the
+/// total number of cups and the number of rounds to play, like `9 10`. If
+/// that line is absent, the given defaults are used.
+fn parse_input(
+    input: &str,
+    default_max_cup: usize,
+    default_rounds: usize,
+) -> anyhow::Result<(Vec<u8>, usize, usize)> {
+    let mut lines = input.lines();
+    let cups = lines.next().context("No cups in input")?;
+    let cups = digits(cups.trim()).context("Failed to parse cups")?;
+
+    if cups.is_empty() {
+        bail!("No cups in input");
+    }
+
+    let mut sorted = cups.clone();
+    sorted.sort_unstable();
+
+    if let Some(pair) = sorted.windows(2).find(|pair| pair[0] == pair[1]) {
+        bail!("Cup {} appears more than once", pair[0]);
+    }
+
+    if sorted
+        .iter()
+        .zip(1..)
+        .any(|(&cup, expected)| cup != expected)
+    {
+        bail!("Cups must be labeled 1 through {}", cups.len());
+    }
+
+    let largest_label = cups.len();
+
+    match lines
+        .next()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+    {
+        None => Ok((cups, default_max_cup, default_rounds)),
+        Some(line) => {
+            let mut params = line.split_whitespace();
+
+            let max_cup = params
+                .next()
+                .context("No cup count")?
+                .parse()
+                .context("Failed to parse cup count")?;
+
+            let rounds = params
+                .next()
+                .context("No round count")?
+                .parse()
+                .context("Failed to parse round count")?;
+
+            if max_cup < largest_label {
+                bail!(
+                    "Cup count {} is smaller than the largest cup label, {}",
+                    max_cup,
+                    largest_label
+                );
+            }
+
+            Ok((cups, max_cup, rounds))
+        }
+    }
 }
 
 pub fn part1(input: &str) -> anyhow::Result<impl Display> {
-    let result = run_simulation(input, 9, 100);
+    let (cups, max_cup, rounds) = parse_input(input, 9, 100)?;
+    let result = run_simulation(&cups, max_cup, rounds)?;
     Ok(result.print())
 }
 
-pub fn part2(input: &str) -> anyhow::Result<String> {
-    let result = run_simulation(input, 1_000_000, 10_000_000);
+pub fn part2(input: &str) -> anyhow::Result<u64> {
+    let (cups, max_cup, rounds) = parse_input(input, 1_000_000, 10_000_000)?;
+    let result = run_simulation(&cups, max_cup, rounds)?;
     let winner1 = result.next_cup(1);
     let winner2 = result.next_cup(winner1);
 
-    Ok(format!(
-        "W1: {}, W2: {}, product: {}",
-        winner1,
-        winner2,
-        winner1 * winner2,
-    ))
+    // The product can be up to ~10^12, so make sure we do the multiplication
+    // in a u64 even on 32 bit targets
+    Ok(winner1 as u64 * winner2 as u64)
+}
+
+#[test]
+fn test_part1_custom_params() {
+    assert_eq!(part1("389125467\n9 10").unwrap().to_string(), "92658374");
+
+    // The cups missing from the input are filled in after it
+    assert_eq!(part1("12345\n9 10").unwrap().to_string(), "37825496");
+}
+
+#[test]
+fn test_parse_input_bad_labels() {
+    // The cup count must include every labeled cup
+    assert!(parse_input("389125467\n5 10", 9, 100).is_err());
+
+    // Labels must be distinct
+    assert!(parse_input("3891254677", 9, 100).is_err());
+
+    // Labels must be 1 through N
+    assert!(parse_input("2345", 9, 100).is_err());
+    assert!(parse_input("1235", 9, 100).is_err());
+}
+
+#[test]
+fn test_part2_memory_limit() {
+    use crate::library::memory::{set_max_memory, MemoryLimitExceeded};
+
+    set_max_memory(Some(1024));
+    let result = part2("389125467");
+    set_max_memory(None);
+
+    assert!(result
+        .unwrap_err()
+        .downcast_ref::<MemoryLimitExceeded>()
+        .is_some());
 }
@@ -1,11 +1,6 @@
-use std::{
-    collections::{HashMap, HashSet},
-    mem,
-};
+use std::{collections::HashSet, mem};
 
 use anyhow::Context;
-use bitvec::__count_elts;
-use gridly::prelude::*;
 use nom::{
     branch::alt,
     character::complete::{multispace0, multispace1},
@@ -20,6 +15,12 @@ use nom_supreme::{
     tag::complete::tag,
 };
 
+use crate::library::{
+    automaton,
+    hex::{Axial, NEIGHBORS},
+};
+
+/// The six hex directions, in the same clockwise order as `hex::NEIGHBORS`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum HexDirection {
     East,
@@ -32,29 +33,11 @@ enum HexDirection {
 
 use HexDirection::*;
 
-static ALL_HEX_DIRECTIONS: [HexDirection; 6] =
-    [East, Southeast, Southwest, West, Northwest, Northeast];
-
-impl VectorLike for HexDirection {
+impl HexDirection {
+    /// The axial offset of one step in this direction
     #[inline]
-    fn rows(&self) -> Rows {
-        match *self {
-            Southeast | Southwest => Rows(1),
-            East | West => Rows(0),
-            Northeast | Northwest => Rows(-1),
-        }
-    }
-
-    fn columns(&self) -> Columns {
-        match *self {
-            Northeast | East => Columns(1),
-            Southeast | Northwest => Columns(0),
-            West | Southwest => Columns(-1),
-        }
-    }
-
-    fn as_vector(&self) -> Vector {
-        self.rows() + self.columns()
+    fn offset(self) -> Axial {
+        NEIGHBORS[self as usize]
     }
 }
 
@@ -76,19 +59,19 @@ fn parse_hex_direction(input: &str) -> IResult<&str, HexDirection, ErrorTree<&st
     .parse(input)
 }
 
-fn parse_direction_list(input: &str) -> IResult<&str, Location, ErrorTree<&str>> {
+fn parse_direction_list(input: &str) -> IResult<&str, Axial, ErrorTree<&str>> {
     parse_separated_terminated(
         parse_hex_direction,
         parse_nothing,
         multispace1,
-        Location::zero,
-        |location, direction| location + direction,
+        || Axial::ORIGIN,
+        |location, direction: HexDirection| location + direction.offset(),
     )
     .context("direction list")
     .parse(input)
 }
 
-fn parse_tile_set(input: &str) -> Result<HashSet<Location>, ErrorTree<final_parser::Location>> {
+fn parse_tile_set(input: &str) -> Result<HashSet<Axial>, ErrorTree<final_parser::Location>> {
     final_parser(
         parse_separated_terminated(
             parse_direction_list,
@@ -106,45 +89,146 @@ fn parse_tile_set(input: &str) -> Result<HashSet<Location>, ErrorTree<final_pars
     )(input)
 }
 
+/// Get the set of tiles that are flipped to black by the instructions in the
+/// input.
+pub fn black_tiles(input: &str) -> anyhow::Result<HashSet<Axial>> {
+    parse_tile_set(input).context("Failed to parse tile set")
+}
+
 pub fn part1(input: &str) -> anyhow::Result<usize> {
-    let tiles = parse_tile_set(input).context("Failed to parse tile set")?;
+    let tiles = black_tiles(input)?;
     let num_black = tiles.len();
     Ok(num_black)
 }
 
-pub fn part2(input: &str) -> anyhow::Result<usize> {
-    let mut tiles = parse_tile_set(input).context("Failed to parse tile set")?;
-    let mut next_tiles = HashSet::with_capacity(tiles.len());
-    let mut empty_neighbor_set: HashMap<Location, usize> = HashMap::with_capacity(tiles.len());
-
-    for _ in 0..100 {
-        for &location in &tiles {
-            let mut count = 0;
-
-            ALL_HEX_DIRECTIONS
-                .iter()
-                .map(|&direction| location + direction)
-                .for_each(|neighbor| match tiles.contains(&neighbor) {
-                    true => count += 1,
-                    false => *empty_neighbor_set.entry(neighbor).or_default() += 1,
-                });
-
-            if count > 0 && count <= 2 {
-                next_tiles.insert(location);
-            }
+/// Run the hex tile automaton for `days` days. A black tile stays black if
+/// `survive` accepts its number of black neighbors, and a white tile flips to
+/// black if `born` accepts its number of black neighbors.
+fn simulate(
+    tiles: HashSet<Axial>,
+    days: usize,
+    survive: impl Fn(usize) -> bool,
+    born: impl Fn(usize) -> bool,
+) -> HashSet<Axial> {
+    (0..days).fold(tiles, |tiles, _| {
+        automaton::step(
+            &tiles,
+            |&location| location.neighbors(),
+            |black, count| match black {
+                true => survive(count),
+                false => born(count),
+            },
+        )
+    })
+}
+
+/// A set of neighbor counts for the hex tile automaton: a black tile stays
+/// black if its number of black neighbors is in `survive`, and a white tile
+/// flips to black if its number of black neighbors is in `born`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct HexLifeRules {
+    survive: Vec<usize>,
+    born: Vec<usize>,
+}
+
+/// The puzzle's rules: a black tile stays black if it has 1 or 2 black
+/// neighbors, and a white tile flips to black if it has exactly 2.
+impl Default for HexLifeRules {
+    fn default() -> Self {
+        HexLifeRules {
+            survive: vec![1, 2],
+            born: vec![2],
         }
+    }
+}
 
-        next_tiles.extend(
-            empty_neighbor_set
-                .iter()
-                .filter(|&(_, &count)| count == 2)
-                .map(|(&loc, _)| loc),
-        );
+impl HexLifeRules {
+    fn survives(&self, count: usize) -> bool {
+        self.survive.contains(&count)
+    }
 
-        mem::swap(&mut tiles, &mut next_tiles);
-        next_tiles.clear();
-        empty_neighbor_set.clear();
+    fn is_born(&self, count: usize) -> bool {
+        self.born.contains(&count)
     }
+}
+
+/// Get the number of black tiles after each of the first `days` days of the
+/// puzzle's automaton.
+fn black_tile_counts(input: &str, days: usize) -> anyhow::Result<Vec<usize>> {
+    let rules = HexLifeRules::default();
+    let mut tiles = black_tiles(input)?;
+
+    Ok((0..days)
+        .map(|_| {
+            tiles = simulate(
+                mem::take(&mut tiles),
+                1,
+                |count| rules.survives(count),
+                |count| rules.is_born(count),
+            );
+            tiles.len()
+        })
+        .collect())
+}
 
+pub fn part2(input: &str) -> anyhow::Result<usize> {
+    let tiles = black_tiles(input)?;
+    let tiles = simulate(tiles, 100, |n| n == 1 || n == 2, |n| n == 2);
     Ok(tiles.len())
 }
+
+#[cfg(test)]
+const SAMPLE_INPUT: &str = "\
+sesenwnenenewseeswwswswwnenewsewsw
+neeenesenwnwwswnenewnwwsewnenwseswesw
+seswneswswsenwwnwse
+nwnwneseeswswnenewneswwnewseswneseene
+swweswneswnenwsewnwneneseenw
+eesenwseswswnenwswnwnwsewwnwsene
+sewnenenenesenwsewnenwwwse
+wenwwweseeeweswwwnwwe
+wsweesenenewnwwnwsenewsenwwsesesenwne
+neeswseenwwswnwswswnw
+nenwswwsewswnenenewsenwsenwnesesenew
+enewnwewneswsewnwswenweswnenwsenwsw
+sweneswneswneneenwnewenewwneswswnese
+swwesenesewenwneswnwwneseswwne
+enesenwswwswneneswsenwnewswseenwsese
+wnwnesenesenenwwnenwsewesewsesesew
+nenewswnwewswnenesenwnesewesw
+eneswnwswnwsenenwnwnwwseeswneewsenese
+neswnwewnwnwseenwseesewsenwsweewe
+wseweeenwnesenwwwswnew
+";
+
+#[test]
+fn test_part1_sample() {
+    assert_eq!(part1(SAMPLE_INPUT).unwrap(), 10);
+}
+
+#[test]
+fn test_part1_counts_black_tiles() {
+    let tiles = black_tiles(SAMPLE_INPUT).unwrap();
+
+    assert_eq!(part1(SAMPLE_INPUT).unwrap(), tiles.len());
+}
+
+#[test]
+fn test_part2_sample() {
+    assert_eq!(part2(SAMPLE_INPUT).unwrap(), 2208);
+}
+
+#[test]
+fn test_black_tile_counts() {
+    let counts = black_tile_counts(SAMPLE_INPUT, 20).unwrap();
+
+    assert_eq!(counts[..10], [15, 12, 25, 14, 23, 28, 41, 37, 49, 37]);
+    assert_eq!(counts[19], 132);
+}
+
+#[test]
+fn test_simulate_empty_rules() {
+    let tiles: HashSet<Axial> = vec![Axial::ORIGIN, East.offset()].into_iter().collect();
+
+    assert!(simulate(tiles, 1, |_| false, |_| false).is_empty());
+}
@@ -1,20 +1,61 @@
-use std::unreachable;
+use std::collections::HashMap;
 
 use anyhow::Context;
 
-fn extract_loop_number(subject: i64, target: i64) -> i64 {
+const MODULUS: i64 = 20201227;
+
+/// Compute (base ^ exponent) % modulus, by repeated squaring
+fn mod_pow(mut base: i64, mut exponent: i64, modulus: i64) -> i64 {
+    let mut result = 1;
+    base %= modulus;
+
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = (result * base) % modulus;
+        }
+
+        base = (base * base) % modulus;
+        exponent >>= 1;
+    }
+
+    result
+}
+
+/// Find the loop size that transforms the subject into the target; that is,
+/// the discrete logarithm of target, base subject. Uses baby-step giant-step,
+/// which is O(√p) instead of the O(p) of repeatedly multiplying.
+fn extract_loop_number(subject: i64, target: i64) -> Option<i64> {
+    let step_size = (MODULUS as f64).sqrt().ceil() as i64;
+
+    // Baby steps: subject^j for each j in 0..step_size
+    let mut baby_steps: HashMap<i64, i64> = HashMap::with_capacity(step_size as usize);
     let mut value = 1;
 
-    for i in 0.. {
-        if value == target {
-            return i;
+    for j in 0..step_size {
+        baby_steps.entry(value).or_insert(j);
+        value = (value * subject) % MODULUS;
+    }
+
+    // Giant steps: target * subject^(-step_size * i). The modulus is prime, so
+    // the inverse of subject^step_size is subject^(p - 1 - step_size)
+    let giant_step = mod_pow(subject, MODULUS - 1 - step_size, MODULUS);
+    let mut value = target % MODULUS;
+
+    for i in 0..step_size {
+        if let Some(&j) = baby_steps.get(&value) {
+            return Some(i * step_size + j);
         }
 
-        value *= subject;
-        value %= 20201227;
+        value = (value * giant_step) % MODULUS;
     }
 
-    unreachable!()
+    None
+}
+
+#[test]
+fn test_extract_loop_number() {
+    assert_eq!(extract_loop_number(7, 5764801), Some(8));
+    assert_eq!(extract_loop_number(7, 17807724), Some(11));
 }
 
 fn perform_operation(subject: i64, loop_size: i64) -> i64 {
@@ -22,7 +63,7 @@ fn perform_operation(subject: i64, loop_size: i64) -> i64 {
 
     for _ in 0..loop_size {
         value *= subject;
-        value %= 20201227;
+        value %= MODULUS;
     }
 
     value
@@ -42,14 +83,16 @@ pub fn part1(input: &str) -> anyhow::Result<i64> {
         .parse()
         .context("couldn't parse door")?;
 
-    let card_loop = extract_loop_number(7, card_pub);
-    let door_loop = extract_loop_number(7, door_pub);
+    let card_loop = extract_loop_number(7, card_pub).context("No loop size for card")?;
+    let door_loop = extract_loop_number(7, door_pub).context("No loop size for door")?;
 
     let encrypt = perform_operation(perform_operation(7, card_loop), door_loop);
 
     Ok(encrypt)
 }
 
-pub fn part2(input: &str) -> anyhow::Result<i64> {
-    todo!()
+/// There's no puzzle for day 25 part 2; the star is awarded for free once all
+/// 49 other stars have been collected.
+pub fn part2(_input: &str) -> anyhow::Result<&'static str> {
+    Ok("Merry Christmas!")
 }
@@ -1,9 +1,11 @@
 use std::iter;
 
-use anyhow::Context;
+use anyhow::bail;
 use gridly::prelude::*;
 use gridly_grids::VecGrid;
 
+use crate::library::grid::parse_grid;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Cell {
     Empty,
@@ -11,15 +13,11 @@ enum Cell {
 }
 
 fn read_grid(input: &str) -> anyhow::Result<VecGrid<Cell>> {
-    let lines = input.lines();
-    let rows = lines.map(|line| {
-        line.chars().map(|c| match c {
-            '.' => Cell::Empty,
-            '#' => Cell::Tree,
-            c => panic!("Invalid cell: {}", c),
-        })
-    });
-    VecGrid::new_from_rows(rows).context("error constructing grid from input")
+    parse_grid(input, |c| match c {
+        '.' => Ok(Cell::Empty),
+        '#' => Ok(Cell::Tree),
+        c => bail!("Invalid cell: {}", c),
+    })
 }
 
 fn count_trees(map: &impl Grid<Item = Cell>, motion: Vector) -> usize {
@@ -6,9 +6,8 @@ use nom::{
     bytes::complete::tag,
     // bytes::complete::take_while_m_n,
     character::complete::char,
-    character::complete::one_of,
+    character::complete::multispace0,
     combinator::all_consuming,
-    combinator::opt,
     error::ParseError,
     multi::fold_many0,
     sequence::{pair, preceded, terminated},
@@ -16,6 +15,8 @@ use nom::{
     Parser,
 };
 
+use crate::library;
+
 // use crate::common::BoolExt;
 
 fn passport_field<'a, E>(label: &'static str) -> impl Parser<&'a str, &'a str, E>
@@ -145,7 +146,7 @@ impl PartialDocument<'_> {
 }
 
 fn parse_document(input: &str) -> IResult<&str, PartialDocument> {
-    let parse_field = terminated(parse_field, opt(one_of(" \n")));
+    let parse_field = terminated(parse_field, multispace0);
 
     fold_many0(
         parse_field,
@@ -168,8 +169,7 @@ fn parse_document(input: &str) -> IResult<&str, PartialDocument> {
 }
 
 pub fn part1(input: &str) -> anyhow::Result<usize> {
-    let count = input
-        .split("\n\n")
+    let count = library::sections(input)
         .enumerate()
         .filter(|&(idx, line)| {
             let (_, document) = all_consuming(parse_document)(line)
@@ -184,8 +184,7 @@ pub fn part1(input: &str) -> anyhow::Result<usize> {
 
 /*
 pub fn part2(input: &str) -> anyhow::Result<usize> {
-    let count = input
-        .split("\n\n")
+    let count = library::sections(input)
         .enumerate()
         .filter_map(|(idx, line)| {
             let (_, document) = all_consuming(parse_document)(line)
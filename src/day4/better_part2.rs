@@ -10,7 +10,7 @@ use nom::{
     bytes::complete::{is_not, take_while_m_n},
     character::complete::{char, digit1, multispace1},
     combinator::{all_consuming, eof, map_opt, map_res, value, verify},
-    error::{FromExternalError, ParseError},
+    error::{ErrorKind, FromExternalError, ParseError},
     multi::fold_many0,
     sequence::{preceded, terminated, tuple},
     IResult, Parser,
@@ -23,6 +23,8 @@ use nom_supreme::{
     tag::{complete::tag, TagError},
 };
 
+use crate::library;
+
 fn passport_field<'a, E>(label: &'static str) -> impl Parser<&'a str, &'a str, E>
 where
     E: ParseError<&'a str> + TagError<&'a str, &'static str>,
@@ -36,23 +38,55 @@ enum Height {
     In(u32),
 }
 
-fn parse_height_unit<'a, E>(
-    unit: &'static str,
-    variant: impl Fn(u32) -> Height,
+/// Parse any of the tags in `table`, returning the item paired with it. Like
+/// `alt`, the tags are tried in order, so a tag must come before any other
+/// tag that is a prefix of it.
+fn parse_from_table<'a, T, E>(table: &'static [(&'static str, T)]) -> impl Parser<&'a str, T, E>
+where
+    T: Copy,
+    E: ParseError<&'a str> + TagError<&'a str, &'static str>,
+{
+    move |input: &'a str| {
+        let mut error: Option<E> = None;
+
+        for &(label, item) in table {
+            match tag(label).parse(input) {
+                Ok((tail, _)) => return Ok((tail, item)),
+                Err(nom::Err::Error(err)) => {
+                    error = Some(match error {
+                        None => err,
+                        Some(error) => error.or(err),
+                    })
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        Err(nom::Err::Error(error.unwrap_or_else(|| {
+            E::from_error_kind(input, ErrorKind::Alt)
+        })))
+    }
+}
+
+/// The units a height can be given in
+static HEIGHT_UNITS: [(&str, fn(u32) -> Height); 2] = [("cm", Height::Cm), ("in", Height::In)];
+
+/// Parse a height, which is a number followed by one of `units`
+fn parse_height_in<'a, E>(
+    units: &'static [(&'static str, fn(u32) -> Height)],
 ) -> impl Parser<&'a str, Height, E>
 where
     E: ParseError<&'a str>
         + TagError<&'a str, &'static str>
         + FromExternalError<&'a str, ParseIntError>,
 {
-    parse_from_str(digit1).terminated(tag(unit)).map(variant)
+    parse_from_str(digit1)
+        .and(parse_from_table(units))
+        .map(|(value, unit)| unit(value))
 }
 
 fn parse_height(input: &str) -> IResult<&str, Height, ErrorTree<&str>> {
-    alt((
-        parse_height_unit("cm", Height::Cm),
-        parse_height_unit("in", Height::In),
-    ))(input)
+    parse_height_in(&HEIGHT_UNITS).parse(input)
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
@@ -88,16 +122,18 @@ enum EyeColor {
     Other,
 }
 
+static EYE_COLORS: [(&str, EyeColor); 7] = [
+    ("amb", EyeColor::Amber),
+    ("blu", EyeColor::Blue),
+    ("brn", EyeColor::Brown),
+    ("gry", EyeColor::Grey),
+    ("grn", EyeColor::Green),
+    ("hzl", EyeColor::Hazel),
+    ("oth", EyeColor::Other),
+];
+
 fn parse_eye_color(input: &str) -> IResult<&str, EyeColor, ErrorTree<&str>> {
-    alt((
-        value(EyeColor::Amber, tag("amb")),
-        value(EyeColor::Blue, tag("blu")),
-        value(EyeColor::Brown, tag("brn")),
-        value(EyeColor::Grey, tag("gry")),
-        value(EyeColor::Green, tag("grn")),
-        value(EyeColor::Hazel, tag("hzl")),
-        value(EyeColor::Other, tag("oth")),
-    ))(input)
+    parse_from_table(&EYE_COLORS).parse(input)
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -220,9 +256,59 @@ fn parse_document(input: &str) -> Result<Document, ErrorTree<Location>> {
     ))(input)
 }
 
+/// Parse and validate each passport in the input, along with its index.
+/// Invalid passports keep their parse errors, for debugging.
+fn validate_documents(input: &str) -> Vec<(usize, Result<Document, ErrorTree<Location>>)> {
+    library::sections(input)
+        .map(parse_document)
+        .enumerate()
+        .collect()
+}
+
 pub fn part2(input: &str) -> anyhow::Result<usize> {
-    Ok(input
-        .split("\n\n")
-        .filter_map(|batch| parse_document(batch).ok())
+    Ok(validate_documents(input)
+        .iter()
+        .filter(|(_index, result)| result.is_ok())
         .count())
 }
+
+#[test]
+fn test_parse_field_tables() {
+    assert_eq!(
+        parse_field("hgt:74in").unwrap(),
+        ("", Field::Height(Height::In(74)))
+    );
+    assert_eq!(
+        parse_field("hgt:180cm").unwrap(),
+        ("", Field::Height(Height::Cm(180)))
+    );
+    assert_eq!(
+        parse_field("ecl:hzl").unwrap(),
+        ("", Field::EyeColor(EyeColor::Hazel))
+    );
+
+    assert!(parse_field("hgt:180").is_err());
+    assert!(parse_field("hgt:180mm").is_err());
+    assert!(parse_field("ecl:xyz").is_err());
+}
+
+#[test]
+fn test_validate_documents() {
+    let input = concat!(
+        "eyr:1972 cid:100\n",
+        "hcl:#18171d ecl:amb hgt:170 pid:186cm iyr:2018 byr:1926\n",
+        "\n",
+        "pid:087499704 hgt:74in ecl:grn iyr:2012 eyr:2030 byr:1980\n",
+        "hcl:#623a2f\n",
+    );
+
+    let results = validate_documents(input);
+
+    assert_eq!(results.len(), 2);
+
+    assert_eq!(results[0].0, 0);
+    assert!(results[0].1.is_err());
+
+    assert_eq!(results[1].0, 1);
+    assert_eq!(results[1].1.as_ref().unwrap().height, Height::In(74));
+}
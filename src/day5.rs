@@ -1,4 +1,4 @@
-use std::str::FromStr;
+use std::{collections::HashSet, str::FromStr};
 
 use anyhow::Context;
 use thiserror::Error;
@@ -124,16 +124,49 @@ fn evaluate_instructions(
         .get()
 }
 
-pub fn part1(input: &str) -> anyhow::Result<i32> {
-    let boarding_passes: Vec<BoardingPass> = parse_items_ws(input)?;
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("seat id {id} appears on more than one boarding pass")]
+struct DuplicateSeat {
+    id: i32,
+}
+
+/// Check that no seat id appears more than once. A duplicate would mean that
+/// the input is bad, since every passenger has their own seat.
+fn check_unique(ids: &[i32]) -> Result<(), DuplicateSeat> {
+    let mut seen = HashSet::with_capacity(ids.len());
+
+    ids.iter()
+        .try_for_each(|&id| seen.insert(id).then_err(DuplicateSeat { id }))
+}
 
-    boarding_passes
+/// Find the highest seat id. If `check_duplicates` is set, also make sure
+/// that no seat id appears more than once.
+fn highest_seat_id(input: &str, check_duplicates: bool) -> anyhow::Result<i32> {
+    let boarding_passes: Vec<BoardingPass> = parse_items_ws(input)?;
+    let seat_ids: anyhow::Result<Vec<i32>> = boarding_passes
         .into_iter()
-        .map(|pass| (pass, pass.seat_id()))
-        .try_fold(0, |current_id, (pass, id)| {
-            id.map(|id| current_id.max(id))
+        .map(|pass| {
+            pass.seat_id()
                 .with_context(|| format!("couldn't get seat id for {:?}", pass))
         })
+        .collect();
+    let seat_ids = seat_ids?;
+
+    if check_duplicates {
+        check_unique(&seat_ids).context("Invalid boarding passes")?;
+    }
+
+    Ok(seat_ids.into_iter().fold(0, i32::max))
+}
+
+pub fn part1(input: &str) -> anyhow::Result<i32> {
+    highest_seat_id(input, false)
+}
+
+/// Same as `part1`, but fail if any two boarding passes share a seat. This is
+/// what the `--strict` flag runs.
+pub fn part1_checked(input: &str) -> anyhow::Result<i32> {
+    highest_seat_id(input, true)
 }
 
 pub fn part2(input: &str) -> anyhow::Result<i32> {
@@ -157,3 +190,26 @@ pub fn part2(input: &str) -> anyhow::Result<i32> {
         })
         .context("Couldn't find seat")
 }
+
+#[test]
+fn test_check_unique() {
+    assert_eq!(check_unique(&[567, 119, 820]), Ok(()));
+    assert_eq!(
+        check_unique(&[567, 119, 820, 119]),
+        Err(DuplicateSeat { id: 119 })
+    );
+}
+
+#[test]
+fn test_highest_seat_id_duplicates() {
+    let input = "FBFBBFFRLR\nBFFFBBFRRR\nFBFBBFFRLR\n";
+
+    assert_eq!(highest_seat_id(input, false).unwrap(), 567);
+    assert!(highest_seat_id(input, true).is_err());
+    assert_eq!(part1(input).unwrap(), 567);
+    assert!(part1_checked(input).is_err());
+    assert_eq!(
+        highest_seat_id("FBFBBFFRLR\nBFFFBBFRRR\n", true).unwrap(),
+        567
+    );
+}
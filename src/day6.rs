@@ -1,8 +1,9 @@
 use std::collections::HashSet;
 
+use crate::library::sections;
+
 pub fn part1(input: &str) -> anyhow::Result<usize> {
-    Ok(input
-        .split("\n\n")
+    Ok(sections(input)
         .map(|group| -> HashSet<char> {
             group.chars().filter(|c| c.is_ascii_alphabetic()).collect()
         })
@@ -11,8 +12,7 @@ pub fn part1(input: &str) -> anyhow::Result<usize> {
 }
 
 pub fn part2(input: &str) -> anyhow::Result<usize> {
-    Ok(input
-        .split("\n\n")
+    Ok(sections(input)
         .map(|group| {
             let mut people = group
                 .split_whitespace()
@@ -28,3 +28,16 @@ pub fn part2(input: &str) -> anyhow::Result<usize> {
         })
         .sum())
 }
+
+#[cfg(test)]
+const SAMPLE_INPUT: &str = "abc\n\na\nb\nc\n\nab\nac\n\na\na\na\na\n\nb\n";
+
+#[test]
+fn test_part1_sample() {
+    assert_eq!(part1(SAMPLE_INPUT).unwrap(), 11);
+}
+
+#[test]
+fn test_part2_sample() {
+    assert_eq!(part2(SAMPLE_INPUT).unwrap(), 6);
+}
@@ -1,13 +1,18 @@
-use std::{convert::TryInto, num::ParseIntError, str::FromStr};
+use std::{convert::TryInto, fmt::Write};
 
 use anyhow::{bail, Context};
 use bitvec::{bitvec, vec::BitVec};
-
+use nom::{
+    branch::alt,
+    character::complete::{digit1, one_of, space0, space1},
+    combinator::{opt, recognize},
+    sequence::pair,
+    IResult, Parser,
+};
+use nom_supreme::{error::ErrorTree, parse_from_str, parser_ext::ParserExt, tag::complete::tag};
 use thiserror::Error;
 
-#[derive(Debug, Clone, Error)]
-#[error("Unrecognized Instruction")]
-struct BadInstruction;
+use crate::library::nom::indexed_lines;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Instruction {
@@ -16,29 +21,31 @@ enum Instruction {
     Noop,
 }
 
-impl FromStr for Instruction {
-    type Err = BadInstruction;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "acc" => Ok(Instruction::Accum),
-            "jmp" => Ok(Instruction::Jmp),
-            "nop" => Ok(Instruction::Noop),
-            _ => Err(BadInstruction),
+impl Instruction {
+    fn name(&self) -> &'static str {
+        match *self {
+            Instruction::Accum => "acc",
+            Instruction::Jmp => "jmp",
+            Instruction::Noop => "nop",
         }
     }
 }
 
-#[derive(Debug, Clone, Error)]
-enum BadOperation {
-    #[error("Invalid operation format")]
-    BadFormat,
-
-    #[error("Error parsing instruction for operation")]
-    Instruction(#[from] BadInstruction),
+fn parse_instruction(input: &str) -> IResult<&str, Instruction, ErrorTree<&str>> {
+    alt((
+        tag("acc").value(Instruction::Accum),
+        tag("jmp").value(Instruction::Jmp),
+        tag("nop").value(Instruction::Noop),
+    ))
+    .context("instruction")
+    .parse(input)
+}
 
-    #[error("Error parsing argument for operation")]
-    Argument(#[from] ParseIntError),
+/// Parse a signed argument, like +5 or -3
+fn parse_argument(input: &str) -> IResult<&str, i32, ErrorTree<&str>> {
+    parse_from_str(recognize(pair(opt(one_of("+-")), digit1)))
+        .context("argument")
+        .parse(input)
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -47,24 +54,18 @@ struct Operation {
     argument: i32,
 }
 
-impl FromStr for Operation {
-    type Err = BadOperation;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut parts = s.split_whitespace();
-
-        let instruction = parts.next().ok_or(BadOperation::BadFormat)?.parse()?;
-        let argument = parts.next().ok_or(BadOperation::BadFormat)?.parse()?;
-
-        if parts.next().is_some() {
-            return Err(BadOperation::BadFormat);
-        }
-
-        Ok(Operation {
+/// Parse an operation, like "acc +5"
+fn parse_operation(input: &str) -> IResult<&str, Operation, ErrorTree<&str>> {
+    parse_instruction
+        .terminated(space1)
+        .and(parse_argument)
+        .delimited_by(space0)
+        .map(|(instruction, argument)| Operation {
             instruction,
             argument,
         })
-    }
+        .context("operation")
+        .parse(input)
 }
 
 enum MachineTermination {
@@ -145,6 +146,23 @@ impl Machine {
         }
     }
 
+    /// Same as `run`, but also returns the instruction pointer of each
+    /// instruction that was executed, in order.
+    pub fn run_traced(&mut self) -> (Vec<usize>, Result<MachineTermination, MachineError>) {
+        let mut trace = Vec::new();
+
+        loop {
+            // If step returns None, an instruction was executed, which means
+            // the ip was in bounds.
+            let ip = self.ip as usize;
+
+            match self.step() {
+                None => trace.push(ip),
+                Some(done) => return (trace, done),
+            }
+        }
+    }
+
     /// Swap a jmp to a nop or a nop to a jmp at the target. Returns None if
     /// the instruction is a acc. Panics if target is out of range.
     pub fn mutate(mut self, target: usize) -> Option<Self> {
@@ -160,15 +178,67 @@ impl Machine {
     }
 }
 
-fn load_code(input: &str) -> anyhow::Result<Vec<Operation>> {
-    input
+/// Render a trace from `Machine::run_traced` as CSV, with the accumulator value
+/// after each step. `code` must be the program that produced the trace.
+fn trace_to_csv(trace: &[usize], code: &[Operation]) -> String {
+    let mut csv = String::from("step,ip,instruction,arg,accum_after\n");
+    let mut accum = 0;
+
+    for (step, &ip) in trace.iter().enumerate() {
+        let op = &code[ip];
+
+        if op.instruction == Instruction::Accum {
+            accum += op.argument;
+        }
+
+        writeln!(
+            csv,
+            "{},{},{},{},{}",
+            step,
+            ip,
+            op.instruction.name(),
+            op.argument,
+            accum
+        )
+        .unwrap();
+    }
+
+    csv
+}
+
+#[test]
+fn test_trace_to_csv() {
+    let code = load_code(concat!(
+        "nop +0\n",
+        "acc +1\n",
+        "jmp +4\n",
+        "acc +3\n",
+        "jmp -3\n",
+        "acc -99\n",
+        "acc +1\n",
+        "jmp -4\n",
+        "acc +6\n",
+    ))
+    .expect("Error loading program");
+
+    let mut machine = Machine::new(code.clone());
+    let (trace, _) = machine.run_traced();
+
+    assert_eq!(trace, [0, 1, 2, 6, 7, 3, 4]);
+
+    let csv = trace_to_csv(&trace, &code);
+    let ips: Vec<&str> = csv
         .lines()
-        .enumerate()
-        .map(|(index, op)| {
-            op.parse()
-                .with_context(|| format!("error parsing instruction index {}", index))
-        })
-        .collect()
+        .skip(1)
+        .map(|row| row.split(',').nth(1).unwrap())
+        .collect();
+
+    assert_eq!(ips, ["0", "1", "2", "6", "7", "3", "4"]);
+    assert_eq!(csv.lines().last(), Some("6,4,jmp,-3,5"));
+}
+
+fn load_code(input: &str) -> anyhow::Result<Vec<Operation>> {
+    indexed_lines(parse_operation)(input).context("error parsing instructions")
 }
 
 pub fn part1(input: &str) -> anyhow::Result<i32> {
@@ -182,6 +252,28 @@ pub fn part1(input: &str) -> anyhow::Result<i32> {
     }
 }
 
+/// Run the program until it terminates or loops, and render every step it
+/// took as CSV. This is what the `--explain` flag prints for part 1.
+pub fn explain_part1(input: &str) -> anyhow::Result<String> {
+    let program = load_code(input).context("error loading program")?;
+
+    let mut machine = Machine::new(program.clone());
+    let (trace, result) = machine.run_traced();
+    result?;
+
+    Ok(trace_to_csv(&trace, &program))
+}
+
+#[test]
+fn test_explain_part1() {
+    let csv = explain_part1("nop +0\nacc +1\njmp -2\n").unwrap();
+
+    assert_eq!(
+        csv,
+        "step,ip,instruction,arg,accum_after\n0,0,nop,0,0\n1,1,acc,1,1\n2,2,jmp,-2,1\n"
+    );
+}
+
 use rayon::prelude::*;
 pub fn part2(input: &str) -> anyhow::Result<i32> {
     let program = load_code(input).context("error loading program")?;
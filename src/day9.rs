@@ -1,4 +1,4 @@
-use std::{cmp::Ordering, collections::VecDeque};
+use std::{cmp::Ordering, collections::VecDeque, io::BufRead, iter};
 
 use anyhow::{bail, Context};
 
@@ -59,14 +59,18 @@ impl XmasDecoder {
     }
 }
 
+/// The size of the preamble in the real puzzle input. The sample input uses a
+/// smaller preamble.
+const PREAMBLE_SIZE: usize = 25;
+
 /// Rather than hardcode our part 1 solution into part 2, we refactor it out
 /// to here so that part 2 can make use of it.
-fn solve_part_1(stream: &[i64]) -> anyhow::Result<i64> {
+fn solve_part_1(stream: &[i64], preamble_size: usize) -> anyhow::Result<i64> {
     let mut stream = stream.iter().copied();
 
     let mut decoder = XmasDecoder::default();
     decoder.reserve(stream.len());
-    decoder.seed(stream.by_ref().take(25));
+    decoder.seed(stream.by_ref().take(preamble_size));
 
     for value in stream {
         if let false = decoder.process(value) {
@@ -77,14 +81,41 @@ fn solve_part_1(stream: &[i64]) -> anyhow::Result<i64> {
     bail!("No solution found");
 }
 
+/// Like `solve_part_1`, but reads and parses the values one line at a time,
+/// so that only the preamble is held in memory.
+fn solve_part1_streaming(reader: impl BufRead, window: usize) -> anyhow::Result<i64> {
+    let mut decoder = XmasDecoder::default();
+    decoder.reserve(window);
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line.with_context(|| format!("Failed to read line {}", index))?;
+        let line = line.trim();
+
+        if line.is_empty() {
+            continue;
+        }
+
+        let value: i64 = line
+            .parse()
+            .with_context(|| format!("Failed to parse line {}: {:?}", index, line))?;
+
+        if decoder.preamble.len() < window {
+            decoder.seed(iter::once(value));
+        } else if !decoder.process(value) {
+            return Ok(value);
+        }
+    }
+
+    bail!("No solution found");
+}
+
 pub fn part1(input: &str) -> anyhow::Result<i64> {
-    let stream: Vec<i64> = parse_items_lines(input)?;
-    solve_part_1(&stream)
+    solve_part1_streaming(input.as_bytes(), PREAMBLE_SIZE)
 }
 
-pub fn part2(input: &str) -> anyhow::Result<i64> {
-    let stream: Vec<i64> = parse_items_lines(input).context("Error parsing input")?;
-    let target = solve_part_1(&stream).context("Couldn't get target vulnerability")?;
+fn solve_part_2(stream: &[i64], preamble_size: usize) -> anyhow::Result<i64> {
+    let target =
+        solve_part_1(stream, preamble_size).context("Couldn't get target vulnerability")?;
 
     let mut rolling_sum = 0;
     let mut head = 0;
@@ -126,3 +157,36 @@ pub fn part2(input: &str) -> anyhow::Result<i64> {
         }
     }
 }
+
+pub fn part2(input: &str) -> anyhow::Result<i64> {
+    let stream: Vec<i64> = parse_items_lines(input).context("Error parsing input")?;
+    solve_part_2(&stream, PREAMBLE_SIZE)
+}
+
+#[cfg(test)]
+const SAMPLE_INPUT: &[i64] = &[
+    35, 20, 15, 25, 47, 40, 62, 55, 65, 95, 102, 117, 150, 182, 127, 219, 299, 277, 309, 576,
+];
+
+#[test]
+fn test_part1_sample() {
+    assert_eq!(solve_part_1(SAMPLE_INPUT, 5).unwrap(), 127);
+}
+
+#[test]
+fn test_part2_sample() {
+    assert_eq!(solve_part_2(SAMPLE_INPUT, 5).unwrap(), 62);
+}
+
+#[test]
+fn test_part1_streaming_sample() {
+    let input: String = SAMPLE_INPUT
+        .iter()
+        .map(|value| format!("{}\n", value))
+        .collect();
+
+    assert_eq!(
+        solve_part1_streaming(std::io::Cursor::new(input), 5).unwrap(),
+        127
+    );
+}
@@ -0,0 +1,59 @@
+//! Fetching puzzle inputs from adventofcode.com. Inputs are cached in the
+//! `inputs` directory, so that each day's input is only downloaded once.
+
+use std::{fs, io, path::PathBuf};
+
+use anyhow::{bail, Context};
+
+fn cache_path(day: u8) -> PathBuf {
+    PathBuf::from("inputs").join(format!("day{}.txt", day))
+}
+
+/// Get the input for a day, downloading it with the given session token if
+/// it isn't already cached.
+pub fn fetch_input(day: u8, session: Option<&str>) -> anyhow::Result<String> {
+    let path = cache_path(day);
+
+    match fs::read_to_string(&path) {
+        Ok(input) => return Ok(input),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+        Err(err) => {
+            return Err(err)
+                .with_context(|| format!("Failed to read cached input '{}'", path.display()))
+        }
+    }
+
+    let session = session
+        .context("Downloading input requires a session token; use --session or set AOC_SESSION")?;
+
+    let url = format!("https://adventofcode.com/2020/day/{}/input", day);
+    let response = ureq::get(&url)
+        .set("Cookie", &format!("session={}", session))
+        .call();
+
+    if response.synthetic() {
+        bail!("Failed to connect to {}: {}", url, response.status_text());
+    }
+
+    let input = match response.status() {
+        200 => response
+            .into_string()
+            .context("Failed to read downloaded input")?,
+        404 => bail!(
+            "There's no input for day {}; is the puzzle unlocked yet?",
+            day
+        ),
+        status => bail!(
+            "Failed to download input from {}: {} {}",
+            url,
+            status,
+            response.status_text()
+        ),
+    };
+
+    fs::create_dir_all("inputs").context("Failed to create input cache directory")?;
+    fs::write(&path, &input)
+        .with_context(|| format!("Failed to cache input to '{}'", path.display()))?;
+
+    Ok(input)
+}
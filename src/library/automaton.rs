@@ -0,0 +1,67 @@
+//! Helpers for running cellular automata over sparse sets of live cells
+
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+};
+
+/// Compute the next generation of a cellular automaton. `live` is the set of
+/// currently live cells, `neighbors` returns the neighbors of a cell, and
+/// `survives` is given whether a cell is currently alive and how many of its
+/// neighbors are alive, and returns whether that cell is alive in the next
+/// generation.
+///
+/// Only live cells and their neighbors are examined, so a dead cell with no
+/// live neighbors can never come alive.
+pub fn step<C, I>(
+    live: &HashSet<C>,
+    neighbors: impl Fn(&C) -> I,
+    survives: impl Fn(bool, usize) -> bool,
+) -> HashSet<C>
+where
+    C: Clone + Eq + Hash,
+    I: IntoIterator<Item = C>,
+{
+    // Each live cell adds one to the count of each of its neighbors, so every
+    // cell with at least one live neighbor (live or not) appears in this map
+    // exactly once, with its full count.
+    let mut neighbor_counts: HashMap<C, usize> = HashMap::with_capacity(live.len() * 2);
+
+    for cell in live {
+        for neighbor in neighbors(cell) {
+            *neighbor_counts.entry(neighbor).or_default() += 1;
+        }
+    }
+
+    // Live cells with no live neighbors are the only ones missing from the map
+    let isolated = live
+        .iter()
+        .filter(|&cell| !neighbor_counts.contains_key(cell))
+        .filter(|_| survives(true, 0))
+        .cloned()
+        .collect::<Vec<C>>();
+
+    neighbor_counts
+        .into_iter()
+        .filter(|(cell, count)| survives(live.contains(cell), *count))
+        .map(|(cell, _)| cell)
+        .chain(isolated)
+        .collect()
+}
+
+#[test]
+fn test_blinker() {
+    let neighbors = |&(x, y): &(isize, isize)| {
+        (-1..=1)
+            .flat_map(move |dx| (-1..=1).map(move |dy| (x + dx, y + dy)))
+            .filter(move |&cell| cell != (x, y))
+    };
+
+    let life = |alive, count| matches!((alive, count), (true, 2) | (_, 3));
+
+    let horizontal: HashSet<(isize, isize)> = [(-1, 0), (0, 0), (1, 0)].iter().copied().collect();
+    let vertical: HashSet<(isize, isize)> = [(0, -1), (0, 0), (0, 1)].iter().copied().collect();
+
+    assert_eq!(step(&horizontal, neighbors, life), vertical);
+    assert_eq!(step(&vertical, neighbors, life), horizontal);
+}
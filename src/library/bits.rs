@@ -0,0 +1,100 @@
+//! Helpers for manipulating individual bits of an i64. Bit indexes count
+//! from 0 at the least significant bit; all of these functions panic if the
+//! index is outside of the 64 bits of an i64.
+
+const BITS: usize = 64;
+
+fn check_index(index: usize) {
+    assert!(
+        index < BITS,
+        "bit index {} is out of range for a {} bit value",
+        index,
+        BITS
+    );
+}
+
+/// Get an i64 with only the bit at `index` set
+pub fn bit(index: usize) -> i64 {
+    check_index(index);
+    1 << index
+}
+
+/// Set the bit at `index`
+pub fn set_bit(value: i64, index: usize) -> i64 {
+    value | bit(index)
+}
+
+/// Clear the bit at `index`
+pub fn clear_bit(value: i64, index: usize) -> i64 {
+    value & !bit(index)
+}
+
+/// Flip the bit at `index`
+// Rounds out the set of bit operations; day 14 only ever sets and clears.
+#[allow(dead_code)]
+pub fn toggle_bit(value: i64, index: usize) -> i64 {
+    value ^ bit(index)
+}
+
+/// Count the set bits in `value`
+#[allow(dead_code)]
+pub fn count_ones(value: i64) -> u32 {
+    value.count_ones()
+}
+
+#[test]
+fn test_bit() {
+    assert_eq!(bit(0), 1);
+    assert_eq!(bit(35), 0b1000_0000_0000_0000_0000_0000_0000_0000_0000);
+    assert_eq!(bit(63), i64::MIN);
+}
+
+#[test]
+#[should_panic(expected = "bit index 64 is out of range")]
+fn test_bit_out_of_range() {
+    bit(64);
+}
+
+#[test]
+fn test_set_bit() {
+    assert_eq!(set_bit(0b1000, 0), 0b1001);
+    assert_eq!(set_bit(0b1001, 0), 0b1001);
+}
+
+#[test]
+#[should_panic(expected = "bit index 70 is out of range")]
+fn test_set_bit_out_of_range() {
+    set_bit(0, 70);
+}
+
+#[test]
+fn test_clear_bit() {
+    assert_eq!(clear_bit(0b1001, 3), 0b0001);
+    assert_eq!(clear_bit(0b0001, 3), 0b0001);
+    assert_eq!(clear_bit(-1, 63), i64::MAX);
+}
+
+#[test]
+#[should_panic(expected = "bit index 64 is out of range")]
+fn test_clear_bit_out_of_range() {
+    clear_bit(0, 64);
+}
+
+#[test]
+fn test_toggle_bit() {
+    assert_eq!(toggle_bit(0b1001, 1), 0b1011);
+    assert_eq!(toggle_bit(0b1011, 1), 0b1001);
+}
+
+#[test]
+#[should_panic(expected = "bit index 64 is out of range")]
+fn test_toggle_bit_out_of_range() {
+    toggle_bit(0, 64);
+}
+
+#[test]
+fn test_count_ones() {
+    assert_eq!(count_ones(0), 0);
+    assert_eq!(count_ones(0b1011), 3);
+    assert_eq!(count_ones(-1), 64);
+}
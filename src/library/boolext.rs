@@ -1,4 +1,4 @@
-/// Helper trait for converting from `bool` to `Option`.
+/// Helper trait for converting from `bool` to `Option` or `Result`.
 pub trait BoolExt: Sized {
     /// If the bool is true, return the result of `func`, wrapped in `Some`;
     /// otherwise return `None`.
@@ -7,6 +7,23 @@ pub trait BoolExt: Sized {
     fn then_some<T>(self, value: T) -> Option<T> {
         self.then(move || value)
     }
+
+    /// If the bool is true, return `Ok(())`; otherwise return `err`.
+    fn then_err<E>(self, err: E) -> Result<(), E> {
+        self.then_or_err((), err)
+    }
+
+    /// If the bool is true, return `value`, wrapped in `Ok`; otherwise return
+    /// `err`.
+    fn then_or_err<T, E>(self, value: T, err: E) -> Result<T, E> {
+        self.then_some(value).ok_or(err)
+    }
+
+    /// Like `then_or_err`, but the error is only created if the bool is
+    /// false. Use this when the error is expensive to build.
+    fn then_or_else_err<T, E, F: FnOnce() -> E>(self, value: T, err: F) -> Result<T, E> {
+        self.then_some(value).ok_or_else(err)
+    }
 }
 
 impl BoolExt for bool {
@@ -18,3 +35,24 @@ impl BoolExt for bool {
         }
     }
 }
+
+#[test]
+fn test_then_err() {
+    assert_eq!(true.then_err("error"), Ok(()));
+    assert_eq!(false.then_err("error"), Err("error"));
+}
+
+#[test]
+fn test_then_or_err() {
+    assert_eq!(true.then_or_err(10, "error"), Ok(10));
+    assert_eq!(false.then_or_err(10, "error"), Err("error"));
+}
+
+#[test]
+fn test_then_or_else_err() {
+    assert_eq!(
+        true.then_or_else_err(10, || -> &'static str { panic!("error built eagerly") }),
+        Ok(10)
+    );
+    assert_eq!(false.then_or_else_err(10, || "error"), Err("error"));
+}
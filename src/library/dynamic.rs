@@ -1,15 +1,20 @@
 //! A library for doing dynamic programming in a non-recursive way
 
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{hash_map::RandomState, BTreeMap, HashMap},
     convert::Infallible,
     error::Error,
     fmt::{self, Debug, Display, Formatter},
-    hash::{BuildHasher, Hash},
+    hash::{BuildHasher, Hash, Hasher},
     marker::PhantomData,
+    rc::Rc,
 };
 
 pub trait SubtaskStore<K, V> {
+    /// The type `execute` uses to track the goals waiting on dependencies
+    /// while it runs, for circular dependency checks
+    type Pending: PendingGoals<K>;
+
     /// Add a new subtask solution to the store. Return the old solution, if
     /// present.
     fn add(&mut self, goal: K, solution: V) -> Option<V>;
@@ -26,6 +31,8 @@ where
     K: Eq + Hash,
     S: Default + BuildHasher,
 {
+    type Pending = HashedPendingGoals<K>;
+
     fn add(&mut self, goal: K, solution: V) -> Option<V> {
         self.insert(goal, solution)
     }
@@ -40,6 +47,8 @@ where
 }
 
 impl<K: Ord, V> SubtaskStore<K, V> for BTreeMap<K, V> {
+    type Pending = Vec<K>;
+
     fn add(&mut self, goal: K, solution: V) -> Option<V> {
         self.insert(goal, solution)
     }
@@ -53,6 +62,151 @@ impl<K: Ord, V> SubtaskStore<K, V> for BTreeMap<K, V> {
     }
 }
 
+/// A store for solutions which are large, or which are depended on by many
+/// other tasks. Solutions are stored in an `Rc`, so a task that needs to keep
+/// a subtask's solution can cheaply clone the `Rc` rather than the solution
+/// itself. Tasks using this store should have `Rc<V>` as their solution type.
+// The current tasks all have small, cheaply cloned solutions, so for now only
+// the tests use this store.
+#[allow(dead_code)]
+#[derive(Debug)]
+pub struct RcStore<K, V> {
+    solutions: HashMap<K, Rc<V>>,
+}
+
+#[allow(dead_code)]
+impl<K, V> RcStore<K, V> {
+    pub fn new() -> Self {
+        RcStore {
+            solutions: HashMap::new(),
+        }
+    }
+}
+
+impl<K, V> Default for RcStore<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Eq + Hash, V> SubtaskStore<K, Rc<V>> for RcStore<K, V> {
+    type Pending = HashedPendingGoals<K>;
+
+    fn add(&mut self, goal: K, solution: Rc<V>) -> Option<Rc<V>> {
+        self.solutions.insert(goal, solution)
+    }
+
+    fn get(&self, goal: &K) -> Option<&Rc<V>> {
+        self.solutions.get(goal)
+    }
+
+    fn contains(&self, goal: &K) -> bool {
+        self.solutions.contains_key(goal)
+    }
+}
+
+/// A stack of goals that are waiting on their dependencies
+pub trait PendingGoals<K>: Default {
+    fn push(&mut self, goal: K);
+    fn pop(&mut self) -> Option<K>;
+
+    /// Check if a goal is anywhere in the stack
+    fn contains(&self, goal: &K) -> bool;
+}
+
+/// Pending goals for keys that can only be compared for equality. Checking
+/// if a goal is pending is linear in the depth of the stack.
+impl<K: PartialEq> PendingGoals<K> for Vec<K> {
+    fn push(&mut self, goal: K) {
+        self.push(goal)
+    }
+
+    fn pop(&mut self) -> Option<K> {
+        self.pop()
+    }
+
+    fn contains(&self, goal: &K) -> bool {
+        self.iter().any(|pending| pending == goal)
+    }
+}
+
+/// Pending goals for hashable keys. A count of the hashes of the pending
+/// goals is kept alongside the stack, so a goal that isn't pending can be
+/// ruled out in constant time; the stack is only scanned when a goal's hash
+/// matches one of the pending goals.
+#[derive(Debug)]
+pub struct HashedPendingGoals<K> {
+    goals: Vec<K>,
+    hashes: HashMap<u64, usize>,
+    hasher: RandomState,
+}
+
+impl<K> Default for HashedPendingGoals<K> {
+    fn default() -> Self {
+        HashedPendingGoals {
+            goals: Vec::new(),
+            hashes: HashMap::new(),
+            hasher: RandomState::new(),
+        }
+    }
+}
+
+impl<K: Hash> HashedPendingGoals<K> {
+    fn hash(&self, goal: &K) -> u64 {
+        let mut hasher = self.hasher.build_hasher();
+        goal.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+impl<K: Eq + Hash> PendingGoals<K> for HashedPendingGoals<K> {
+    fn push(&mut self, goal: K) {
+        *self.hashes.entry(self.hash(&goal)).or_default() += 1;
+        self.goals.push(goal);
+    }
+
+    fn pop(&mut self) -> Option<K> {
+        let goal = self.goals.pop()?;
+        let hash = self.hash(&goal);
+
+        match self.hashes.get_mut(&hash) {
+            Some(count) if *count > 1 => *count -= 1,
+            _ => {
+                self.hashes.remove(&hash);
+            }
+        }
+
+        Some(goal)
+    }
+
+    fn contains(&self, goal: &K) -> bool {
+        self.hashes.contains_key(&self.hash(goal)) && self.goals.contains(goal)
+    }
+}
+
+#[test]
+fn test_hashed_pending_goals() {
+    let mut pending = HashedPendingGoals::default();
+
+    pending.push('a');
+    pending.push('b');
+    pending.push('a');
+
+    assert!(pending.contains(&'a'));
+    assert!(pending.contains(&'b'));
+    assert!(!pending.contains(&'c'));
+
+    assert_eq!(pending.pop(), Some('a'));
+    assert!(pending.contains(&'a'));
+
+    assert_eq!(pending.pop(), Some('b'));
+    assert!(!pending.contains(&'b'));
+
+    assert_eq!(pending.pop(), Some('a'));
+    assert!(!pending.contains(&'a'));
+    assert_eq!(pending.pop(), None);
+}
+
 #[derive(Debug)]
 pub struct Dependency<'a, K> {
     key: K,
@@ -80,6 +234,11 @@ pub trait Subtask<K, V> {
 }
 
 pub trait Task<K, V, E> {
+    /// Partial progress for a single goal. If `solve` is interrupted by a
+    /// dependency, whatever it left in `state` is handed back to it when it
+    /// is retried for the same goal, so work done before requesting the
+    /// dependency doesn't need to be repeated. `state` is always `None` on
+    /// the first attempt at a goal.
     type State;
 
     fn solve<'sub, T>(
@@ -125,6 +284,10 @@ pub enum DynamicError<K, E> {
 
     /// The solver itself returned an error
     Error(E),
+
+    /// The dependency stack grew deeper than the maximum given to
+    /// `execute_bounded`
+    DepthExceeded(usize),
 }
 
 impl<K: Debug, E> Display for DynamicError<K, E> {
@@ -134,6 +297,13 @@ impl<K: Debug, E> Display for DynamicError<K, E> {
                 write!(f, "goal {:?} has a circular dependency on itself", dep)
             }
             DynamicError::Error(..) => write!(f, "solver encountered an error"),
+            DynamicError::DepthExceeded(depth) => {
+                write!(
+                    f,
+                    "dependency stack exceeded the maximum depth of {}",
+                    depth
+                )
+            }
         }
     }
 }
@@ -142,6 +312,7 @@ impl<K: Debug, E: Error + 'static> Error for DynamicError<K, E> {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match *self {
             DynamicError::CircularDependency(..) => None,
+            DynamicError::DepthExceeded(..) => None,
             DynamicError::Error(ref err) => Some(err),
         }
     }
@@ -176,8 +347,6 @@ where
     }
 }
 
-// TODO: Add an algorithm for state preservation when retrying tasks
-
 /// Solve a dynamic algorithm.
 ///
 /// This will run task.solve(&goal, subtasker). The task can request subgoal
@@ -193,16 +362,54 @@ where
 /// store can fulfill them all. To prevent wasting work finding a partial
 /// solution, you can call `subtasker.precheck(iter)?` at the beginning of
 /// your Task::solve implementation with an iterator over all the subgoal
-/// dependencies you're expecting
-pub fn execute<K: PartialEq, V, E, T: Task<K, V, E>, S: SubtaskStore<K, V>>(
+/// dependencies you're expecting. If the dependencies can't be known up
+/// front, the task can instead save its progress in its `Task::State`, which
+/// is preserved on the dependency stack along with the goal and restored
+/// when the task is retried.
+pub fn execute<K, V, E, T: Task<K, V, E>, S: SubtaskStore<K, V>>(
+    goal: K,
+    task: &T,
+    store: S,
+) -> Result<V, DynamicError<K, E>> {
+    execute_with_store(goal, task, store).map(|(solution, _store)| solution)
+}
+
+/// Solve a dynamic algorithm, like `execute`, and also return the store,
+/// which will contain the solutions to every subtask solved along the way.
+pub fn execute_with_store<K, V, E, T: Task<K, V, E>, S: SubtaskStore<K, V>>(
+    goal: K,
+    task: &T,
+    store: S,
+) -> Result<(V, S), DynamicError<K, E>> {
+    execute_impl(goal, task, store, usize::MAX)
+}
+
+/// Solve a dynamic algorithm, like `execute`, but fail with
+/// `DynamicError::DepthExceeded` if more than `max_depth` goals are ever
+/// waiting on their dependencies at once, rather than growing without bound.
+// The day 7 and day 10 dependency chains are shallow enough for `execute`.
+#[allow(dead_code)]
+pub fn execute_bounded<K, V, E, T: Task<K, V, E>, S: SubtaskStore<K, V>>(
     goal: K,
     task: &T,
     store: S,
+    max_depth: usize,
 ) -> Result<V, DynamicError<K, E>> {
+    execute_impl(goal, task, store, max_depth).map(|(solution, _store)| solution)
+}
+
+fn execute_impl<K, V, E, T: Task<K, V, E>, S: SubtaskStore<K, V>>(
+    goal: K,
+    task: &T,
+    store: S,
+    max_depth: usize,
+) -> Result<(V, S), DynamicError<K, E>> {
     let mut subtasker = Subtasker { store };
 
-    // TODO: use an ordered hash map for faster circular checks
-    let mut dependency_stack = vec![];
+    // The goals waiting on dependencies, and their saved states. These are
+    // always pushed and popped together.
+    let mut pending_goals = S::Pending::default();
+    let mut pending_states = vec![];
     let mut current_goal = goal;
     let mut current_state = None;
 
@@ -218,8 +425,8 @@ pub fn execute<K: PartialEq, V, E, T: Task<K, V, E>, S: SubtaskStore<K, V>>(
         // doesn't happen.
 
         match task.solve(&current_goal, &subtasker, &mut current_state) {
-            Ok(solution) => match dependency_stack.pop() {
-                None => break Ok(solution),
+            Ok(solution) => match pending_goals.pop().zip(pending_states.pop()) {
+                None => break Ok((solution, subtasker.store)),
                 Some((dependent_goal, state)) => {
                     subtasker.store.add(current_goal, solution);
                     current_goal = dependent_goal;
@@ -228,8 +435,12 @@ pub fn execute<K: PartialEq, V, E, T: Task<K, V, E>, S: SubtaskStore<K, V>>(
             },
             Err(TaskInterrupt::Error(err)) => break Err(DynamicError::Error(err)),
             Err(TaskInterrupt::Dependency(Dependency { key: subgoal, .. })) => {
-                dependency_stack.push((current_goal, current_state));
-                match dependency_stack.iter().any(|(goal, ..)| *goal == subgoal) {
+                pending_goals.push(current_goal);
+                pending_states.push(current_state);
+                if pending_states.len() > max_depth {
+                    break Err(DynamicError::DepthExceeded(max_depth));
+                }
+                match pending_goals.contains(&subgoal) {
                     true => break Err(DynamicError::CircularDependency(subgoal)),
                     false => {
                         current_goal = subgoal;
@@ -240,3 +451,198 @@ pub fn execute<K: PartialEq, V, E, T: Task<K, V, E>, S: SubtaskStore<K, V>>(
         }
     }
 }
+
+#[test]
+fn test_rc_store_diamond() {
+    use std::cell::Cell;
+
+    /// 'd' depends on 'b' and 'c', which both depend on 'a'
+    struct Diamond {
+        bottom_solves: Cell<u32>,
+    }
+
+    impl StatelessTask<char, Rc<Vec<u32>>, Infallible> for Diamond {
+        fn solve<'sub, T>(
+            &self,
+            goal: &char,
+            subtasker: &'sub T,
+        ) -> Result<Rc<Vec<u32>>, TaskInterrupt<'sub, char, Infallible>>
+        where
+            T: Subtask<char, Rc<Vec<u32>>>,
+        {
+            match *goal {
+                'a' => {
+                    self.bottom_solves.set(self.bottom_solves.get() + 1);
+                    Ok(Rc::new(vec![1; 1000]))
+                }
+                'b' | 'c' => Ok(Rc::clone(subtasker.solve('a')?)),
+                _ => {
+                    subtasker.precheck(vec!['b', 'c'])?;
+                    let b = subtasker.solve('b')?;
+                    let c = subtasker.solve('c')?;
+
+                    assert!(Rc::ptr_eq(b, c));
+                    Ok(Rc::new(vec![b.len() as u32 + c.len() as u32]))
+                }
+            }
+        }
+    }
+
+    let task = Diamond {
+        bottom_solves: Cell::new(0),
+    };
+
+    let solution = execute('d', &task, RcStore::new()).unwrap();
+
+    assert_eq!(*solution, [2000]);
+    assert_eq!(task.bottom_solves.get(), 1);
+}
+
+#[test]
+fn test_state_preserved_across_dependencies() {
+    use std::cell::Cell;
+
+    /// Each goal does some expensive work, then depends on the goal below it
+    struct Chain {
+        solves: Cell<u32>,
+        expensive_work: Cell<u32>,
+    }
+
+    impl Task<u32, u32, Infallible> for Chain {
+        type State = u32;
+
+        fn solve<'sub, T>(
+            &self,
+            goal: &u32,
+            subtasker: &'sub T,
+            state: &mut Option<u32>,
+        ) -> Result<u32, TaskInterrupt<'sub, u32, Infallible>>
+        where
+            T: Subtask<u32, u32>,
+        {
+            self.solves.set(self.solves.get() + 1);
+
+            let work = *state.get_or_insert_with(|| {
+                self.expensive_work.set(self.expensive_work.get() + 1);
+                goal * 10
+            });
+
+            match *goal {
+                0 => Ok(work),
+                goal => Ok(work + subtasker.solve(goal - 1)?),
+            }
+        }
+    }
+
+    let task = Chain {
+        solves: Cell::new(0),
+        expensive_work: Cell::new(0),
+    };
+
+    let solution = execute(5, &task, HashMap::new()).unwrap();
+
+    assert_eq!(solution, 150);
+
+    // Every goal except 0 is interrupted once by its dependency
+    assert_eq!(task.solves.get(), 11);
+
+    // ...but the expensive work is only done once per goal
+    assert_eq!(task.expensive_work.get(), 6);
+}
+
+#[test]
+fn test_execute_with_store() {
+    /// The sum of all the numbers from 0 to the goal
+    struct Triangle;
+
+    impl StatelessTask<u32, u32, Infallible> for Triangle {
+        fn solve<'sub, T>(
+            &self,
+            goal: &u32,
+            subtasker: &'sub T,
+        ) -> Result<u32, TaskInterrupt<'sub, u32, Infallible>>
+        where
+            T: Subtask<u32, u32>,
+        {
+            match *goal {
+                0 => Ok(0),
+                goal => Ok(goal + subtasker.solve(goal - 1)?),
+            }
+        }
+    }
+
+    let (solution, store) = execute_with_store(4, &Triangle, BTreeMap::new()).unwrap();
+
+    assert_eq!(solution, 10);
+
+    // The store has every subtask, but not the original goal
+    assert_eq!(
+        store.into_iter().collect::<Vec<_>>(),
+        [(0, 0), (1, 1), (2, 3), (3, 6)]
+    );
+}
+
+#[test]
+fn test_execute_bounded() {
+    /// Each goal depends on the goal below it
+    struct Countdown;
+
+    impl StatelessTask<u32, u32, Infallible> for Countdown {
+        fn solve<'sub, T>(
+            &self,
+            goal: &u32,
+            subtasker: &'sub T,
+        ) -> Result<u32, TaskInterrupt<'sub, u32, Infallible>>
+        where
+            T: Subtask<u32, u32>,
+        {
+            match *goal {
+                0 => Ok(0),
+                goal => Ok(*subtasker.solve(goal - 1)?),
+            }
+        }
+    }
+
+    assert!(matches!(
+        execute_bounded(5, &Countdown, HashMap::new(), 5),
+        Ok(0)
+    ));
+    assert!(matches!(
+        execute_bounded(5, &Countdown, HashMap::new(), 4),
+        Err(DynamicError::DepthExceeded(4))
+    ));
+}
+
+#[test]
+fn test_circular_dependency() {
+    /// 'a' depends on 'b', which depends on 'c', which depends on 'a'
+    struct Cycle;
+
+    impl StatelessTask<char, u32, Infallible> for Cycle {
+        fn solve<'sub, T>(
+            &self,
+            goal: &char,
+            subtasker: &'sub T,
+        ) -> Result<u32, TaskInterrupt<'sub, char, Infallible>>
+        where
+            T: Subtask<char, u32>,
+        {
+            let dependency = match *goal {
+                'a' => 'b',
+                'b' => 'c',
+                _ => 'a',
+            };
+
+            Ok(*subtasker.solve(dependency)?)
+        }
+    }
+
+    assert!(matches!(
+        execute('a', &Cycle, HashMap::new()),
+        Err(DynamicError::CircularDependency('a'))
+    ));
+    assert!(matches!(
+        execute('b', &Cycle, BTreeMap::new()),
+        Err(DynamicError::CircularDependency('b'))
+    ));
+}
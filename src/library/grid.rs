@@ -0,0 +1,221 @@
+//! Functions for reading gridly grids out of puzzle input, and for writing
+//! them back out
+
+use std::{
+    collections::HashSet,
+    io::{self, Write},
+};
+
+use anyhow::{bail, Context};
+use gridly::prelude::*;
+use gridly_grids::VecGrid;
+use joinery::prelude::*;
+
+use super::sections;
+
+/// Parse a block of text into a grid, converting each character with `cell`.
+/// Every line of the input must be the same length. Errors report the row
+/// and column of the offending character.
+pub fn parse_grid<T>(
+    input: &str,
+    cell: impl Fn(char) -> anyhow::Result<T>,
+) -> anyhow::Result<VecGrid<T>> {
+    let mut width = None;
+
+    let rows: Vec<Vec<T>> = input
+        .lines()
+        .enumerate()
+        .map(|(row, line)| {
+            let cells: Vec<T> = line
+                .chars()
+                .enumerate()
+                .map(|(column, c)| {
+                    cell(c).with_context(|| {
+                        format!("invalid cell {:?} at row {}, column {}", c, row, column)
+                    })
+                })
+                .collect::<anyhow::Result<_>>()?;
+
+            match width {
+                None => width = Some(cells.len()),
+                Some(width) if width != cells.len() => bail!(
+                    "row {} has length {}, but the rows before it have length {}",
+                    row,
+                    cells.len(),
+                    width
+                ),
+                Some(..) => {}
+            }
+
+            Ok(cells)
+        })
+        .collect::<anyhow::Result<_>>()?;
+
+    VecGrid::new_from_rows(rows).context("grid is too large")
+}
+
+/// Parse a sequence of labeled grids, separated by blank lines. Each grid is
+/// preceded by a header line ending in a colon, like `Tile 2311:`. The text
+/// of the header (without the colon) is passed to `header`, and each
+/// character of the grid body is passed to `cell`.
+pub fn labeled_grids<'a, H, T>(
+    input: &'a str,
+    mut header: impl FnMut(&'a str) -> anyhow::Result<H>,
+    cell: impl Fn(char) -> anyhow::Result<T>,
+) -> anyhow::Result<Vec<(H, VecGrid<T>)>> {
+    sections(input)
+        .enumerate()
+        .map(|(index, block)| {
+            let (label, body) = match block.split_once('\n') {
+                Some((label, body)) => (label.trim_end_matches('\r'), body),
+                None => bail!("grid {} has no body", index),
+            };
+
+            let label = label
+                .strip_suffix(':')
+                .with_context(|| format!("grid {} header {:?} has no ':'", index, label))?;
+
+            let label = header(label)
+                .with_context(|| format!("failed to parse header of grid {}", index))?;
+
+            let grid = parse_grid(body, &cell)
+                .with_context(|| format!("failed to parse body of grid {}", index))?;
+
+            Ok((label, grid))
+        })
+        .collect()
+}
+
+/// Write a grid of bools as a plain (P1) PBM image, where set cells are
+/// black (1) and unset cells are white (0).
+// A debugging aid for eyeballing large grids; no solution writes images.
+#[allow(dead_code)]
+pub fn write_pbm(grid: &impl Grid<Item = bool>, w: &mut impl Write) -> io::Result<()> {
+    writeln!(w, "P1")?;
+    writeln!(w, "{} {}", grid.num_columns().0, grid.num_rows().0)?;
+
+    for row in grid.rows().iter() {
+        let pixels: Vec<char> = row
+            .iter()
+            .map(|&cell| match cell {
+                true => '1',
+                false => '0',
+            })
+            .collect();
+
+        writeln!(w, "{}", pixels.join_with(' '))?;
+    }
+
+    Ok(())
+}
+
+/// Find all of the orthogonally connected regions of cells for which `matches`
+/// returns true. Each region is returned as the set of its locations.
+// Only the tests label regions so far, but flood fills are a puzzle staple.
+#[allow(dead_code)]
+pub fn components<T>(
+    grid: &impl Grid<Item = T>,
+    matches: impl Fn(&T) -> bool,
+) -> Vec<HashSet<Location>> {
+    let mut seen: HashSet<Location> = HashSet::new();
+    let mut components = Vec::new();
+
+    for row in grid.rows().iter() {
+        for (start, cell) in row.iter_with_locations() {
+            if !matches(cell) || !seen.insert(start) {
+                continue;
+            }
+
+            let mut component = HashSet::new();
+            let mut stack = vec![start];
+
+            while let Some(location) = stack.pop() {
+                component.insert(location);
+
+                for &direction in &EACH_DIRECTION {
+                    let neighbor = location + direction;
+
+                    if let Ok(cell) = grid.get(neighbor) {
+                        if matches(cell) && seen.insert(neighbor) {
+                            stack.push(neighbor);
+                        }
+                    }
+                }
+            }
+
+            components.push(component);
+        }
+    }
+
+    components
+}
+
+#[test]
+fn test_write_pbm() {
+    let grid = VecGrid::new_from_rows(vec![vec![true, false], vec![false, true]]).unwrap();
+    let mut output = Vec::new();
+
+    write_pbm(&grid, &mut output).unwrap();
+
+    assert_eq!(output, b"P1\n2 2\n1 0\n0 1\n");
+}
+
+#[test]
+fn test_components() {
+    let grid = parse_grid("##..\n#..#\n...#\n", |c| Ok(c == '#')).unwrap();
+    let mut sizes: Vec<usize> = components(&grid, |&cell| cell)
+        .iter()
+        .map(|component| component.len())
+        .collect();
+
+    sizes.sort_unstable();
+    assert_eq!(sizes, [2, 3]);
+}
+
+#[test]
+fn test_labeled_grids() {
+    let input = "Tile 12:\n#.\n.#\n..\n\nTile 7:\n##.\n";
+
+    let grids = labeled_grids(
+        input,
+        |header| {
+            header
+                .strip_prefix("Tile ")
+                .context("no 'Tile' prefix")?
+                .parse::<u32>()
+                .context("invalid tile id")
+        },
+        |c| Ok(c == '#'),
+    )
+    .unwrap();
+
+    let cells = |grid: &VecGrid<bool>| -> Vec<Vec<bool>> {
+        grid.rows()
+            .iter()
+            .map(|row| row.iter().copied().collect())
+            .collect()
+    };
+
+    assert_eq!(grids.len(), 2);
+
+    let (id, grid) = &grids[0];
+    assert_eq!(*id, 12);
+    assert_eq!((grid.num_rows().0, grid.num_columns().0), (3, 2));
+    assert_eq!(cells(grid), [[true, false], [false, true], [false, false]]);
+
+    let (id, grid) = &grids[1];
+    assert_eq!(*id, 7);
+    assert_eq!((grid.num_rows().0, grid.num_columns().0), (1, 3));
+    assert_eq!(cells(grid), [[true, true, false]]);
+
+    // Windows line endings split into the same grids
+    let windows = input.replace('\n', "\r\n");
+    let windows_grids =
+        labeled_grids(&windows, |header| Ok(header.to_owned()), |c| Ok(c == '#')).unwrap();
+
+    assert_eq!(windows_grids.len(), 2);
+    assert_eq!(windows_grids[0].0, "Tile 12");
+    assert_eq!(cells(&windows_grids[0].1), cells(&grids[0].1));
+    assert_eq!(windows_grids[1].0, "Tile 7");
+    assert_eq!(cells(&windows_grids[1].1), cells(&grids[1].1));
+}
@@ -0,0 +1,82 @@
+//! Types for working with hexagonal grids, using axial coordinates. The `q`
+//! axis points east and the `r` axis points southeast; the implied third cube
+//! coordinate `s` is such that `q + r + s == 0`.
+
+use std::ops::Add;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Axial {
+    pub q: isize,
+    pub r: isize,
+}
+
+/// The six neighbors of the origin, in clockwise order starting from east.
+pub static NEIGHBORS: [Axial; 6] = [
+    Axial { q: 1, r: 0 },
+    Axial { q: 0, r: 1 },
+    Axial { q: -1, r: 1 },
+    Axial { q: -1, r: 0 },
+    Axial { q: 0, r: -1 },
+    Axial { q: 1, r: -1 },
+];
+
+impl Axial {
+    pub const ORIGIN: Axial = Axial { q: 0, r: 0 };
+
+    // Puzzles so far only walk from the origin, but explicit coordinates are
+    // handy for tests and for anything that rotates a known shape.
+    #[allow(dead_code)]
+    pub fn new(q: isize, r: isize) -> Self {
+        Axial { q, r }
+    }
+
+    /// The third cube coordinate
+    #[allow(dead_code)]
+    pub fn s(&self) -> isize {
+        -self.q - self.r
+    }
+
+    /// Iterate over the six neighbors of this location, in clockwise order
+    /// starting from east.
+    pub fn neighbors(self) -> impl Iterator<Item = Axial> {
+        NEIGHBORS.iter().map(move |&offset| self + offset)
+    }
+
+    /// Rotate this location 60° clockwise around the origin. In cube
+    /// coordinates, this maps (q, r, s) to (-r, -s, -q).
+    // No solution needs rotation yet; day 24 only uses `neighbors`.
+    #[allow(dead_code)]
+    pub fn rotate_cw(self) -> Axial {
+        Axial {
+            q: -self.r,
+            r: -self.s(),
+        }
+    }
+}
+
+impl Add for Axial {
+    type Output = Axial;
+
+    fn add(self, rhs: Axial) -> Axial {
+        Axial {
+            q: self.q + rhs.q,
+            r: self.r + rhs.r,
+        }
+    }
+}
+
+#[test]
+fn test_rotate_cw_cycle() {
+    let start = Axial::new(3, -7);
+    let end = (0..6).fold(start, |location, _| location.rotate_cw());
+
+    assert_ne!(start.rotate_cw(), start);
+    assert_eq!(end, start);
+}
+
+#[test]
+fn test_rotate_cw_neighbors() {
+    for (i, &neighbor) in NEIGHBORS.iter().enumerate() {
+        assert_eq!(neighbor.rotate_cw(), NEIGHBORS[(i + 1) % 6]);
+    }
+}
@@ -0,0 +1,29 @@
+//! Number theory helpers
+
+/// Find the inverse of `a` modulo `m`; that is, the `x` in `0..m` such that
+/// `(a * x) % m == 1`. Uses the extended Euclidean algorithm. Returns None if
+/// `a` and `m` aren't coprime, in which case there is no inverse.
+pub fn mod_inverse(a: i64, m: i64) -> Option<i64> {
+    let mut remainders = (a.rem_euclid(m), m);
+    let mut coefficients = (1, 0);
+
+    while remainders.1 != 0 {
+        let quotient = remainders.0 / remainders.1;
+
+        remainders = (remainders.1, remainders.0 - quotient * remainders.1);
+        coefficients = (coefficients.1, coefficients.0 - quotient * coefficients.1);
+    }
+
+    match remainders.0 {
+        1 => Some(coefficients.0.rem_euclid(m)),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_mod_inverse() {
+    assert_eq!(mod_inverse(3, 7), Some(5));
+    assert_eq!(mod_inverse(10, 17), Some(12));
+    assert_eq!(mod_inverse(-3, 7), Some(2));
+    assert_eq!(mod_inverse(2, 4), None);
+}
@@ -0,0 +1,44 @@
+//! An optional limit on how much memory the allocation-heavy solutions are
+//! allowed to use. Solutions that support it estimate the size of their big
+//! allocations up front and check them against the limit, so that they can
+//! fail with a clean error instead of being killed by the OS.
+
+use std::{cell::Cell, mem};
+
+use thiserror::Error;
+
+thread_local! {
+    // The limit is per-thread so that tests with different limits don't
+    // interfere with each other.
+    static MAX_MEMORY: Cell<Option<usize>> = Cell::new(None);
+}
+
+/// Set (or, with None, remove) the memory limit, in bytes, for solutions run
+/// on the current thread.
+pub fn set_max_memory(limit: Option<usize>) {
+    MAX_MEMORY.with(|max| max.set(limit))
+}
+
+#[derive(Debug, Clone, Error)]
+#[error("{what} would need about {needed} bytes, but the memory limit is {limit} bytes")]
+pub struct MemoryLimitExceeded {
+    what: &'static str,
+    needed: usize,
+    limit: usize,
+}
+
+/// Check that an allocation of `count` values of type `T` fits within the
+/// memory limit, if there is one. `what` describes the allocation for the
+/// error message.
+pub fn check_allocation<T>(what: &'static str, count: usize) -> Result<(), MemoryLimitExceeded> {
+    let needed = count.saturating_mul(mem::size_of::<T>());
+
+    MAX_MEMORY.with(|max| match max.get() {
+        Some(limit) if needed > limit => Err(MemoryLimitExceeded {
+            what,
+            needed,
+            limit,
+        }),
+        _ => Ok(()),
+    })
+}
@@ -2,9 +2,27 @@
 //! have come in handy in the past and will probably continue to do so in the
 //! future
 
+pub mod automaton;
+pub mod bits;
 mod boolext;
 pub mod dynamic;
+pub mod grid;
+pub mod hex;
+pub mod math;
+pub mod memory;
+pub mod nom;
+pub mod parse;
 mod parse_items;
+pub mod pathfind;
+pub mod ranges;
+pub mod recursion;
+mod sections;
+pub mod sums;
 
 pub use boolext::BoolExt;
-pub use parse_items::{parse_items, parse_items_lines, parse_items_ws};
+pub use parse_items::{parse_items, parse_items_lines, parse_items_sep, parse_items_ws};
+pub use sections::sections;
+
+// Not used by any day yet; see `parse_items_all`.
+#[allow(unused_imports)]
+pub use parse_items::parse_items_all;
@@ -0,0 +1,232 @@
+//! Helpers for bridging nom parsers with the rest of the library
+
+use std::{any::type_name, fmt::Display, iter::FromIterator, str::FromStr};
+
+use nom::{
+    bytes::complete::tag_no_case,
+    error::{ErrorKind, FromExternalError, ParseError},
+    IResult, Parser,
+};
+use nom_supreme::{
+    error::ErrorTree,
+    final_parser::{final_parser, Location},
+};
+use num::Bounded;
+use thiserror::Error;
+
+use super::parse_items::ParseItemsError;
+
+/// Create a function that parses each line of its input with `parser`, and
+/// collects the results. Each line must be entirely consumed by the parser.
+/// Like `parse_items_lines`, errors report the index and content of the line
+/// that failed to parse.
+pub fn indexed_lines<'a, O, C>(
+    parser: impl Parser<&'a str, O, ErrorTree<&'a str>>,
+) -> impl FnMut(&'a str) -> Result<C, ParseItemsError<ErrorTree<Location>>>
+where
+    C: FromIterator<O>,
+{
+    let mut parse_line = final_parser(parser);
+
+    move |input| {
+        input
+            .lines()
+            .enumerate()
+            .map(|(index, line)| {
+                parse_line(line).map_err(|error| ParseItemsError::new(index, line, error))
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn test_indexed_lines() {
+    use nom::character::complete::digit1;
+
+    let mut parse_lines = indexed_lines(digit1);
+
+    let lines: Vec<&str> = parse_lines("10\n20\n30\n").unwrap();
+    assert_eq!(lines, ["10", "20", "30"]);
+
+    let error = parse_lines("10\n20\n30\nabc\n50\n")
+        .map(|_: Vec<&str>| ())
+        .unwrap_err();
+
+    assert_eq!(error.index, 3);
+    assert_eq!(error.input, "abc");
+}
+
+/// Extension trait adding `inspect` to every nom parser, alongside the
+/// combinators from `nom_supreme::parser_ext::ParserExt`.
+// Only reached for while debugging a parser, so no solution calls it.
+#[allow(dead_code)]
+pub trait ParserInspectExt<I, O, E>: Parser<I, O, E> + Sized {
+    /// Call `f` with the parser's output each time it succeeds. The result
+    /// of the parser is unchanged. This is useful for `eprintln!` debugging
+    /// of intermediate results in larger parsers.
+    fn inspect<F: Fn(&O)>(self, f: F) -> Inspect<Self, F> {
+        Inspect { parser: self, f }
+    }
+}
+
+impl<I, O, E, P> ParserInspectExt<I, O, E> for P where P: Parser<I, O, E> {}
+
+/// Parser which calls a function with the output of its subparser. Created
+/// by `ParserInspectExt::inspect`.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy)]
+pub struct Inspect<P, F> {
+    parser: P,
+    f: F,
+}
+
+impl<I, O, E, P, F> Parser<I, O, E> for Inspect<P, F>
+where
+    P: Parser<I, O, E>,
+    F: Fn(&O),
+{
+    #[inline]
+    fn parse(&mut self, input: I) -> IResult<I, O, E> {
+        let (tail, value) = self.parser.parse(input)?;
+        (self.f)(&value);
+        Ok((tail, value))
+    }
+}
+
+#[test]
+fn test_inspect() {
+    use std::cell::Cell;
+
+    use nom::character::complete::digit1;
+
+    let seen = Cell::new(None);
+    let mut parser = digit1::<&str, ErrorTree<&str>>.inspect(|&value| seen.set(Some(value)));
+
+    assert_eq!(parser.parse("123abc").unwrap(), ("abc", "123"));
+    assert_eq!(seen.get(), Some("123"));
+}
+
+/// Parse a fixed string, ignoring ASCII case. Returns the slice of the input
+/// that matched, in whatever case it was written.
+pub fn tag_case_insensitive<'a, E: ParseError<&'a str>>(
+    tag: &'static str,
+) -> impl Fn(&'a str) -> IResult<&'a str, &'a str, E> {
+    tag_no_case(tag)
+}
+
+#[test]
+fn test_tag_case_insensitive() {
+    let parser = tag_case_insensitive::<ErrorTree<&str>>("mask");
+
+    assert_eq!(parser("mask = X").unwrap(), (" = X", "mask"));
+    assert_eq!(parser("MaSk = X").unwrap(), (" = X", "MaSk"));
+    assert!(parser("mem[8]").is_err());
+}
+
+/// Error from `parse_bounded_int` when a number is too large, in either
+/// direction, for its target type to hold
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("number too large for {type_name}: {digits:?}")]
+pub struct IntTooLarge {
+    pub type_name: &'static str,
+    pub digits: String,
+}
+
+/// Wrap a parser that recognizes an integer, like `digit1`, and parse the
+/// recognized slice as a `T`. Unlike `parse_from_str`, a well-formed number
+/// that is out of range for `T` fails with an `IntTooLarge` naming the type,
+/// rather than a generic overflow from `FromStr`. Any other failure is
+/// reported with the error from `FromStr`.
+pub fn parse_bounded_int<'a, T, E>(
+    mut recognizer: impl Parser<&'a str, &'a str, E>,
+) -> impl FnMut(&'a str) -> IResult<&'a str, T, E>
+where
+    T: FromStr + Bounded + Display,
+    E: FromExternalError<&'a str, IntTooLarge> + FromExternalError<&'a str, T::Err>,
+{
+    let signed = T::min_value().to_string().starts_with('-');
+
+    move |input| {
+        let (tail, digits) = recognizer.parse(input)?;
+
+        match digits.parse() {
+            Ok(value) => Ok((tail, value)),
+            Err(error) => {
+                let negative = digits.starts_with('-');
+                let magnitude = digits.strip_prefix(&['+', '-'][..]).unwrap_or(digits);
+                let well_formed =
+                    !magnitude.is_empty() && magnitude.bytes().all(|b| b.is_ascii_digit());
+
+                // A well-formed number can only fail to parse if it's out of
+                // range, unless it's negative and T is unsigned.
+                Err(nom::Err::Error(if well_formed && (signed || !negative) {
+                    let error = IntTooLarge {
+                        type_name: type_name::<T>(),
+                        digits: digits.to_owned(),
+                    };
+                    E::from_external_error(input, ErrorKind::TooLarge, error)
+                } else {
+                    E::from_external_error(input, ErrorKind::MapRes, error)
+                }))
+            }
+        }
+    }
+}
+
+#[test]
+fn test_parse_bounded_int() {
+    use nom::character::complete::digit1;
+
+    let mut parser = parse_bounded_int::<i64, ErrorTree<&str>>(digit1);
+
+    assert_eq!(parser("1234]").unwrap(), ("]", 1234));
+    assert_eq!(parser("00000000000000000000000042").unwrap(), ("", 42));
+}
+
+#[test]
+fn test_parse_bounded_int_too_large() {
+    use nom::{
+        character::complete::{char, digit1},
+        combinator::{opt, recognize},
+        sequence::pair,
+    };
+    use nom_supreme::error::BaseErrorKind;
+
+    let examples = [
+        "1234567890123456789012345",
+        // The same number of digits as i64::MAX, but larger
+        "9999999999999999999",
+        "-9999999999999999999",
+    ];
+
+    let mut parser =
+        parse_bounded_int::<i64, ErrorTree<&str>>(recognize(pair(opt(char('-')), digit1)));
+
+    for &input in &examples {
+        match parser(input) {
+            Err(nom::Err::Error(ErrorTree::Base {
+                location,
+                kind: BaseErrorKind::External(error),
+            })) => {
+                assert_eq!(location, input);
+                assert_eq!(
+                    error.to_string(),
+                    format!("number too large for i64: {:?}", input)
+                );
+            }
+            result => panic!("unexpected result for {:?}: {:?}", input, result),
+        }
+    }
+
+    // A negative number isn't too large for an unsigned type; it's invalid
+    let mut parser =
+        parse_bounded_int::<u8, ErrorTree<&str>>(recognize(pair(opt(char('-')), digit1)));
+
+    match parser("-5") {
+        Err(nom::Err::Error(ErrorTree::Base {
+            kind: BaseErrorKind::External(error),
+            ..
+        })) => assert_eq!(error.to_string(), "invalid digit found in string"),
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
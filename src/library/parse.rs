@@ -0,0 +1,25 @@
+//! Small parsers for simple, non-nom puzzle inputs
+
+use anyhow::bail;
+
+/// Parse a contiguous string of decimal digits, like `389125467`, into its
+/// individual digits. Any non-digit character is an error.
+pub fn digits(input: &str) -> anyhow::Result<Vec<u8>> {
+    input
+        .chars()
+        .enumerate()
+        .map(|(position, c)| match c.to_digit(10) {
+            Some(digit) => Ok(digit as u8),
+            None => bail!("invalid digit {:?} at position {}", c, position),
+        })
+        .collect()
+}
+
+#[test]
+fn test_digits() {
+    assert_eq!(digits("389125467").unwrap(), [3, 8, 9, 1, 2, 5, 4, 6, 7]);
+    assert_eq!(
+        digits("12a").unwrap_err().to_string(),
+        "invalid digit 'a' at position 2"
+    );
+}
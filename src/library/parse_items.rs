@@ -9,13 +9,23 @@ use thiserror::Error;
 #[derive(Debug, Clone, Error)]
 #[error("error parsing item {input:?} at index {index}")]
 pub struct ParseItemsError<E: Error + 'static> {
-    index: usize,
-    input: String,
+    pub(super) index: usize,
+    pub(super) input: String,
 
     #[source]
     error: E,
 }
 
+impl<E: Error + 'static> ParseItemsError<E> {
+    pub(crate) fn new(index: usize, input: &str, error: E) -> Self {
+        Self {
+            index,
+            input: input.to_owned(),
+            error,
+        }
+    }
+}
+
 /// `parse_items`, but it specifically parses whitespace separated components
 /// of the input
 pub fn parse_items_ws<T, C>(input: &str) -> Result<C, ParseItemsError<T::Err>>
@@ -37,6 +47,18 @@ where
     parse_items(input.lines())
 }
 
+/// `parse_items`, but it specifically parses components of the input
+/// separated by `sep`. Each component is trimmed of whitespace before being
+/// parsed, and a trailing separator is ignored.
+pub fn parse_items_sep<T, C>(input: &str, sep: char) -> Result<C, ParseItemsError<T::Err>>
+where
+    T: FromStr,
+    C: FromIterator<T>,
+    T::Err: Error,
+{
+    parse_items(input.trim_end().split_terminator(sep).map(str::trim))
+}
+
 /// Advent of Code commonly gives input as a uniform list of separated items.
 /// This parses such a list by takin an iterator of those items and then parsing
 /// each individual element with FromStr. This function is generic over any
@@ -53,11 +75,53 @@ where
         .into_iter()
         .enumerate()
         .map(|(index, value)| {
-            value.parse().map_err(|error| ParseItemsError {
-                index,
-                error,
-                input: value.to_owned(),
-            })
+            value
+                .parse()
+                .map_err(|error| ParseItemsError::new(index, value, error))
         })
         .collect()
 }
+
+/// `parse_items`, but rather than stopping at the first error, it attempts
+/// to parse every item and reports all of the errors it encountered.
+// Meant for validating a whole input file by hand; the solutions themselves
+// are happy to stop at the first bad item.
+#[allow(dead_code)]
+pub fn parse_items_all<'a, I, T, C>(input: I) -> Result<C, Vec<ParseItemsError<T::Err>>>
+where
+    I: IntoIterator<Item = &'a str>,
+    T: FromStr,
+    C: FromIterator<T>,
+    T::Err: Error,
+{
+    let mut errors = Vec::new();
+
+    let items = input
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, value)| match value.parse() {
+            Ok(item) => Some(item),
+            Err(error) => {
+                errors.push(ParseItemsError::new(index, value, error));
+                None
+            }
+        })
+        .collect();
+
+    match errors.is_empty() {
+        true => Ok(items),
+        false => Err(errors),
+    }
+}
+
+#[test]
+fn test_parse_items_all() {
+    let errors = parse_items_all::<_, i32, Vec<i32>>(vec!["1", "a", "3", "b"]).unwrap_err();
+    let indexes: Vec<usize> = errors.iter().map(|error| error.index).collect();
+
+    assert_eq!(indexes, [1, 3]);
+    assert_eq!(
+        parse_items_all::<_, i32, Vec<i32>>(vec!["1", "2"]).unwrap(),
+        [1, 2]
+    );
+}
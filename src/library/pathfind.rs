@@ -0,0 +1,104 @@
+//! Generic shortest-path searches
+
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    hash::Hash,
+    ops::Add,
+};
+
+/// Find the cheapest path from `start` to any node satisfying `is_goal`,
+/// where `neighbors` returns each node reachable from a given node along with
+/// the cost of moving there. Returns the total cost of the path along with
+/// the path itself, including both endpoints, or None if no goal is
+/// reachable. Costs must be non-negative.
+// No day has needed a weighted search yet; this is here for the maze-style
+// puzzles that always turn up eventually.
+#[allow(dead_code)]
+pub fn dijkstra<C, W, I>(
+    start: C,
+    neighbors: impl Fn(&C) -> I,
+    is_goal: impl Fn(&C) -> bool,
+) -> Option<(W, Vec<C>)>
+where
+    C: Clone + Eq + Hash,
+    W: Copy + Ord + Default + Add<Output = W>,
+    I: IntoIterator<Item = (C, W)>,
+{
+    // Nodes are referred to by their index in `nodes`, which means the heap
+    // doesn't need C to be Ord. `costs` and `previous` are indexed the same
+    // way.
+    let mut nodes: Vec<C> = vec![start.clone()];
+    let mut node_ids: HashMap<C, usize> = HashMap::new();
+    let mut costs: Vec<W> = vec![W::default()];
+    let mut previous: Vec<Option<usize>> = vec![None];
+
+    node_ids.insert(start, 0);
+
+    let mut queue = BinaryHeap::new();
+    queue.push(Reverse((W::default(), 0)));
+
+    while let Some(Reverse((cost, id))) = queue.pop() {
+        // We may have found a cheaper path to this node after it was queued
+        if cost > costs[id] {
+            continue;
+        }
+
+        if is_goal(&nodes[id]) {
+            let mut path = Vec::new();
+            let mut current = Some(id);
+
+            while let Some(id) = current {
+                path.push(nodes[id].clone());
+                current = previous[id];
+            }
+
+            path.reverse();
+            return Some((cost, path));
+        }
+
+        for (neighbor, weight) in neighbors(&nodes[id]) {
+            let neighbor_cost = cost + weight;
+
+            match node_ids.get(&neighbor) {
+                Some(&neighbor_id) => {
+                    if neighbor_cost < costs[neighbor_id] {
+                        costs[neighbor_id] = neighbor_cost;
+                        previous[neighbor_id] = Some(id);
+                        queue.push(Reverse((neighbor_cost, neighbor_id)));
+                    }
+                }
+                None => {
+                    let neighbor_id = nodes.len();
+
+                    nodes.push(neighbor.clone());
+                    node_ids.insert(neighbor, neighbor_id);
+                    costs.push(neighbor_cost);
+                    previous.push(Some(id));
+                    queue.push(Reverse((neighbor_cost, neighbor_id)));
+                }
+            }
+        }
+    }
+
+    None
+}
+
+#[test]
+fn test_dijkstra() {
+    let edges = |&node: &char| -> Vec<(char, u32)> {
+        match node {
+            'a' => vec![('b', 1), ('c', 4)],
+            'b' => vec![('c', 2), ('d', 5)],
+            'c' => vec![('d', 1)],
+            _ => vec![],
+        }
+    };
+
+    assert_eq!(
+        dijkstra('a', edges, |&node| node == 'd'),
+        Some((4, vec!['a', 'b', 'c', 'd']))
+    );
+
+    assert_eq!(dijkstra('d', edges, |&node| node == 'a'), None);
+}
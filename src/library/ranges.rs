@@ -0,0 +1,35 @@
+//! Utilities for working with collections of integer ranges
+
+use std::ops::RangeInclusive;
+
+/// Merge a collection of inclusive ranges into the sorted list of disjoint
+/// intervals they cover, as `(min, max)` pairs. Ranges that overlap or are
+/// directly adjacent (like `1..=3` and `4..=5`) are merged together.
+pub fn coverage(ranges: &[RangeInclusive<i64>]) -> Vec<(i64, i64)> {
+    let mut ranges: Vec<(i64, i64)> = ranges
+        .iter()
+        .filter(|range| !range.is_empty())
+        .map(|range| (*range.start(), *range.end()))
+        .collect();
+
+    ranges.sort_unstable();
+
+    let mut merged: Vec<(i64, i64)> = Vec::with_capacity(ranges.len());
+
+    for (min, max) in ranges {
+        match merged.last_mut() {
+            Some((_, last_max)) if min <= last_max.saturating_add(1) => {
+                *last_max = (*last_max).max(max)
+            }
+            _ => merged.push((min, max)),
+        }
+    }
+
+    merged
+}
+
+#[test]
+fn test_coverage() {
+    assert_eq!(coverage(&[1..=3, 2..=5, 8..=9]), [(1, 5), (8, 9)]);
+    assert_eq!(coverage(&[8..=9, 4..=5, 1..=3]), [(1, 5), (8, 9)]);
+}
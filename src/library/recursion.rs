@@ -0,0 +1,54 @@
+//! A guard for limiting the depth of recursive solutions
+
+use thiserror::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("recursion exceeded the depth limit of {limit}")]
+pub struct DepthExceeded {
+    pub limit: usize,
+}
+
+/// The current depth of a recursive computation. Recursive functions take a
+/// `Depth` and pass `depth.descend()?` to each recursive call, which fails
+/// cleanly with `DepthExceeded` rather than overflowing the stack.
+#[derive(Debug, Clone, Copy)]
+pub struct Depth {
+    depth: usize,
+    limit: usize,
+}
+
+impl Depth {
+    /// Get the depth one level deeper than this one, or an error if that
+    /// would exceed the limit.
+    pub fn descend(self) -> Result<Depth, DepthExceeded> {
+        match self.depth < self.limit {
+            true => Ok(Depth {
+                depth: self.depth + 1,
+                limit: self.limit,
+            }),
+            false => Err(DepthExceeded { limit: self.limit }),
+        }
+    }
+}
+
+/// Run a recursive computation which may recurse at most `limit` levels
+/// deep. `f` is given the initial `Depth`.
+pub fn with_depth_limit<T, E>(limit: usize, f: impl FnOnce(Depth) -> Result<T, E>) -> Result<T, E> {
+    f(Depth { depth: 0, limit })
+}
+
+#[test]
+fn test_with_depth_limit() {
+    fn count_down(n: usize, depth: Depth) -> Result<usize, DepthExceeded> {
+        match n {
+            0 => Ok(0),
+            n => Ok(count_down(n - 1, depth.descend()?)? + 1),
+        }
+    }
+
+    assert_eq!(with_depth_limit(10, |depth| count_down(10, depth)), Ok(10));
+    assert_eq!(
+        with_depth_limit(10, |depth| count_down(11, depth)),
+        Err(DepthExceeded { limit: 10 })
+    );
+}
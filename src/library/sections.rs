@@ -0,0 +1,46 @@
+//! Splitting puzzle input into blank-line-separated sections
+
+use std::iter;
+
+const NEWLINES: &[char] = &['\r', '\n'];
+
+/// Split the input into sections separated by blank lines. Both `\n` and
+/// `\r\n` line endings are understood, and leading or trailing blank lines
+/// don't produce empty sections. Each section has its trailing newline
+/// removed.
+pub fn sections(input: &str) -> impl Iterator<Item = &str> {
+    let mut rest = input;
+
+    iter::from_fn(move || {
+        rest = rest.trim_start_matches(NEWLINES);
+
+        if rest.is_empty() {
+            return None;
+        }
+
+        // The section ends at the first newline that is immediately followed
+        // by another (blank) line.
+        let end = rest
+            .match_indices('\n')
+            .map(|(index, _)| index)
+            .find(|&index| {
+                let after = &rest[index + 1..];
+                after.starts_with('\n') || after.starts_with("\r\n")
+            })
+            .unwrap_or(rest.len());
+
+        let (section, tail) = rest.split_at(end);
+        rest = tail;
+
+        Some(section.trim_end_matches(NEWLINES))
+    })
+}
+
+#[test]
+fn test_sections() {
+    let unix: Vec<&str> = sections("ab\nc\n\nd\n\n\ne\n\n").collect();
+    let windows: Vec<&str> = sections("ab\r\nc\r\n\r\nd\r\n\r\n\r\ne\r\n\r\n").collect();
+
+    assert_eq!(unix, ["ab\nc", "d", "e"]);
+    assert_eq!(windows, ["ab\r\nc", "d", "e"]);
+}
@@ -0,0 +1,74 @@
+//! Searches for combinations of values with a particular sum
+
+use std::{
+    collections::BTreeSet,
+    ops::{
+        Bound::{self, Excluded, Unbounded},
+        RangeBounds, Sub,
+    },
+};
+
+/// Find `count` distinct values in `values` that add up to `target`, and
+/// return them in ascending order. All of the values must be positive, which
+/// allows the search to skip any value that's already too large.
+pub fn find_subset_sum<T>(values: &BTreeSet<T>, target: T, count: usize) -> Option<Vec<T>>
+where
+    T: Ord + Copy + Sub<Output = T>,
+{
+    find_subset_sum_above(values, Unbounded, target, count).map(|mut subset| {
+        subset.reverse();
+        subset
+    })
+}
+
+/// Find the subset, using only values above `min`. Returns the subset in
+/// descending order.
+fn find_subset_sum_above<T>(
+    values: &BTreeSet<T>,
+    min: Bound<T>,
+    target: T,
+    count: usize,
+) -> Option<Vec<T>>
+where
+    T: Ord + Copy + Sub<Output = T>,
+{
+    if let Excluded(min) = min {
+        if min >= target {
+            return None;
+        }
+    }
+
+    match count {
+        0 => None,
+        1 => values
+            .get(&target)
+            .filter(|&value| (min, Unbounded).contains(value))
+            .map(|&value| vec![value]),
+        count => values.range((min, Excluded(target))).find_map(|&value| {
+            find_subset_sum_above(values, Excluded(value), target - value, count - 1).map(
+                |mut subset| {
+                    subset.push(value);
+                    subset
+                },
+            )
+        }),
+    }
+}
+
+#[test]
+fn test_find_subset_sum() {
+    let values: BTreeSet<i64> = vec![1721, 979, 366, 299, 675, 1456].into_iter().collect();
+
+    assert_eq!(find_subset_sum(&values, 2020, 2), Some(vec![299, 1721]));
+    assert_eq!(find_subset_sum(&values, 2020, 3), Some(vec![366, 675, 979]));
+    assert_eq!(find_subset_sum(&values, 2020, 4), None);
+}
+
+#[test]
+fn test_find_subset_sum_distinct() {
+    let values: BTreeSet<u32> = vec![5, 10, 15].into_iter().collect();
+
+    // 10 + 10 would work, but there's only one 10
+    assert_eq!(find_subset_sum(&values, 20, 2), Some(vec![5, 15]));
+    assert_eq!(find_subset_sum(&values, 30, 2), None);
+}
@@ -1,5 +1,7 @@
 #![allow(unstable_name_collisions)]
 
+#[cfg(feature = "download")]
+mod download;
 mod library;
 
 mod day1;
@@ -29,6 +31,7 @@ mod day8;
 mod day9;
 
 use std::{
+    fmt::Write as _,
     fs,
     io::{self, Read},
     num::ParseIntError,
@@ -59,6 +62,10 @@ macro_rules! solution_days {
             $($Day,)*
         }
 
+        impl SolutionDay {
+            pub const ALL: &[SolutionDay] = &[$(SolutionDay::$Day,)*];
+        }
+
         impl FromStr for SolutionDay {
             type Err = SolutionDayError;
 
@@ -112,6 +119,24 @@ solution_days! {
     day25
 }
 
+impl SolutionDay {
+    /// Days whose input is a single line of separated tokens, rather than a
+    /// block of lines. Surrounding whitespace (including the trailing newline
+    /// that most files end with) is trimmed from the input before it's given
+    /// to these solutions.
+    pub fn trims_input(self) -> bool {
+        matches!(
+            self,
+            SolutionDay::day15 | SolutionDay::day23 | SolutionDay::day25
+        )
+    }
+
+    /// The number of this day, from 1 to 25
+    pub fn number(self) -> u8 {
+        self as u8 + 1
+    }
+}
+
 #[derive(Debug, Clone, Error)]
 pub enum SolutionPartError {
     #[error("Failed to parse day: {0}")]
@@ -145,15 +170,48 @@ impl FromStr for SolutionPart {
 #[derive(Debug, StructOpt)]
 struct Args {
     /// Which day's solution are you looking for?
-    #[structopt(short, long)]
-    pub day: SolutionDay,
+    #[structopt(short, long, required_unless = "list")]
+    pub day: Option<SolutionDay>,
 
     /// Part 1 or Part 2?
-    #[structopt(short, long)]
-    pub part: SolutionPart,
+    #[structopt(short, long, required_unless = "list")]
+    pub part: Option<SolutionPart>,
+
+    /// Instead of solving anything, list every day and part, and whether it
+    /// has a solution.
+    #[structopt(long)]
+    pub list: bool,
+
+    /// Instead of just the answer, print a breakdown of how it was found.
+    /// Only day 8 part 1, day 13 part 2, and day 17 part 1 support this.
+    #[structopt(long)]
+    pub explain: bool,
+
+    /// Check the input for problems that the solution would otherwise
+    /// ignore, and fail if any are found. Only day 5 part 1 supports this.
+    #[structopt(long)]
+    pub strict: bool,
 
     /// The file from which to read input. If omitted, we read from stdin.
     pub input: Option<PathBuf>,
+
+    /// If no input file is given, download the input from adventofcode.com
+    /// instead of reading from stdin. Downloaded inputs are cached in the
+    /// `inputs` directory.
+    #[cfg(feature = "download")]
+    #[structopt(long)]
+    pub download: bool,
+
+    /// The adventofcode.com session token to download input with. If
+    /// omitted, we use the AOC_SESSION environment variable.
+    #[cfg(feature = "download")]
+    #[structopt(long)]
+    pub session: Option<String>,
+
+    /// The maximum amount of memory, in megabytes, that the solution may
+    /// allocate. Only the allocation-heavy days (15 and 23) check this.
+    #[structopt(long)]
+    pub max_memory: Option<usize>,
 }
 
 macro_rules! solver_picker {
@@ -174,9 +232,108 @@ macro_rules! solver_picker {
     }};
 }
 
+macro_rules! solution_list {
+    ($(
+        $Day:ident { $($Part:ident $(,)?)* }
+    )*) => {
+        &[$($(
+            (SolutionDay::$Day, SolutionPart::$Part),
+        )*)*]
+    };
+}
+
+/// Invoke a macro with the given leading arguments, followed by the list of
+/// every day and part that has solution code. This way, `solver_picker` and
+/// `solution_list` are guaranteed to agree.
+macro_rules! with_solutions {
+    ($macro:ident ! ($($args:tt)*)) => {
+        $macro! (
+            $($args)*
+
+            day1 {part1 part2}
+            day2 {part1 part2}
+            day3 {part1 part2}
+            day4 {part1 part2}
+            day5 {part1 part2}
+            day6 {part1 part2}
+            day7 {part1 part2}
+            day8 {part1 part2}
+            day9 {part1 part2}
+            day10 {part1 part2}
+            day11 {part1 part2}
+            day12 {part1 part2}
+            day13 {part1 part2}
+            day14 {part1 part2}
+            day15 {part1 part2}
+            day16 {part1 part2}
+            day17 {part1 part2}
+            day18 {part1 part2}
+            day19 {part1 part2}
+            day20 {part1 part2}
+            day21 {part1 part2}
+            day22 {part1 part2}
+            day23 {part1 part2}
+            day24 {part1 part2}
+            day25 {part1 part2}
+        )
+    };
+}
+
+/// Solutions that exist, but don't solve a puzzle. Day 25 has no part 2
+/// puzzle, so its part 2 just prints a message.
+fn is_stub(day: SolutionDay, part: SolutionPart) -> bool {
+    matches!((day, part), (SolutionDay::day25, SolutionPart::part2))
+}
+
+/// Render a table of every day and part, showing which have solutions
+fn solution_table(solutions: &[(SolutionDay, SolutionPart)]) -> String {
+    let status = |day, part| match (solutions.contains(&(day, part)), is_stub(day, part)) {
+        (false, _) => "missing",
+        (true, true) => "stub",
+        (true, false) => "solved",
+    };
+
+    let mut table = String::from("day  part 1   part 2\n");
+
+    for &day in SolutionDay::ALL {
+        writeln!(
+            table,
+            "{:>3}  {:<7}  {}",
+            day.number(),
+            status(day, SolutionPart::part1),
+            status(day, SolutionPart::part2),
+        )
+        .unwrap();
+    }
+
+    table
+}
+
+/// Apply the day's input policy before handing the input to its solution.
+/// See `SolutionDay::trims_input`.
+fn prepare_input(day: SolutionDay, input: &str) -> &str {
+    match day.trims_input() {
+        true => input.trim(),
+        false => input,
+    }
+}
+
 fn main() -> anyhow::Result<()> {
     let args: Args = Args::from_args();
 
+    if args.list {
+        print!("{}", solution_table(with_solutions!(solution_list!())));
+        return Ok(());
+    }
+
+    let day = args.day.context("A day is required")?;
+    let part = args.part.context("A part is required")?;
+
+    library::memory::set_max_memory(
+        args.max_memory
+            .map(|megabytes| megabytes.saturating_mul(1024 * 1024)),
+    );
+
     let mut input = String::new();
 
     match args.input {
@@ -187,6 +344,12 @@ fn main() -> anyhow::Result<()> {
             file.read_to_string(&mut input)
                 .with_context(|| format!("Failed to read from input file '{}'", path.display()))?;
         }
+        #[cfg(feature = "download")]
+        None if args.download => {
+            let session = args.session.or_else(|| std::env::var("AOC_SESSION").ok());
+
+            input = download::fetch_input(day.number(), session.as_deref())?;
+        }
         None => {
             io::stdin()
                 .read_to_string(&mut input)
@@ -198,36 +361,73 @@ fn main() -> anyhow::Result<()> {
     // messages can borrow from the input.
     let input: &'static str = Box::leak(input.into_boxed_str());
 
-    solver_picker! (
-        args.day, args.part, &input;
-
-
-        day1 {part1 part2}
-        day2 {part1 part2}
-        day3 {part1 part2}
-        day4 {part1 part2}
-        day5 {part1 part2}
-        day6 {part1 part2}
-        day7 {part1 part2}
-        day8 {part1 part2}
-        day9 {part1 part2}
-        day10 {part1 part2}
-        day11 {part1 part2}
-        day12 {part1 part2}
-        day13 {part1 part2}
-        day14 {part1 part2}
-        day15 {part1 part2}
-        day16 {part1 part2}
-        day17 {part1 part2}
-        day18 {part1 part2}
-        day19 {part1 part2}
-        day20 {part1 part2}
-        day21 {part1 part2}
-        day22 {part1 part2}
-        day23 {part1 part2}
-        day24 {part1 part2}
-        day25 {part1 part2}
-    );
+    let input = prepare_input(day, input);
+
+    if args.explain {
+        match (day, part) {
+            (SolutionDay::day8, SolutionPart::part1) => {
+                print!("{}", day8::explain_part1(input)?)
+            }
+            (SolutionDay::day13, SolutionPart::part2) => {
+                println!("{}", day13::explain_part2(input)?)
+            }
+            (SolutionDay::day17, SolutionPart::part1) => {
+                print!("{}", day17::explain_part1(input)?)
+            }
+            (day, part) => anyhow::bail!("{:?}, {:?} has no explanation", day, part),
+        }
+
+        return Ok(());
+    }
+
+    if args.strict {
+        match (day, part) {
+            (SolutionDay::day5, SolutionPart::part1) => {
+                println!("{}", day5::part1_checked(input)?)
+            }
+            (day, part) => anyhow::bail!("{:?}, {:?} has no strict mode", day, part),
+        }
+
+        return Ok(());
+    }
+
+    with_solutions!(solver_picker!(day, part, &input;));
 
     Ok(())
 }
+
+#[test]
+fn test_day_number() {
+    assert_eq!(SolutionDay::day1.number(), 1);
+    assert_eq!(SolutionDay::day25.number(), 25);
+    assert_eq!("14".parse::<SolutionDay>().unwrap().number(), 14);
+}
+
+#[test]
+fn test_solution_table() {
+    let table = solution_table(with_solutions!(solution_list!()));
+    let lines: Vec<&str> = table.lines().collect();
+
+    assert_eq!(lines.len(), 26);
+    assert_eq!(lines[1], "  1  solved   solved");
+    assert_eq!(lines[25], " 25  solved   stub");
+
+    let table = solution_table(&[(SolutionDay::day3, SolutionPart::part1)]);
+    assert_eq!(table.lines().nth(3), Some("  3  solved   missing"));
+}
+
+#[test]
+fn test_prepare_input() {
+    let input = "0,3,6\n \n";
+
+    assert_eq!(prepare_input(SolutionDay::day15, input), "0,3,6");
+    assert_eq!(
+        day15::part1(prepare_input(SolutionDay::day15, input)).unwrap(),
+        day15::part1("0,3,6").unwrap()
+    );
+
+    // Days that take blocks of lines get their input unchanged, since
+    // trailing blank lines can be significant
+    let input = "ecl:gry pid:860033327\n\n";
+    assert_eq!(prepare_input(SolutionDay::day4, input), input);
+}